@@ -0,0 +1,474 @@
+//! 関数名と引数ごとにレスポンスを保持する
+//!
+//! Argument0を関数名、Argument1以降を引数として、レスポンスを覚えておく。
+//! レスポンスはCharsetとバージョンを持つため、これらが異なるリクエストは別のものとして扱う。
+//! 容量を超えたときは、最も長く使われていないものから捨てる。
+//! `serde` featureを有効にすると、キャッシュ全体を保存・復元できる。
+//!
+//! # Examples
+//!
+//! ```
+//! use saori_interface_rs::*;
+//!
+//! let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: add\r\nArgument1: 1\r\nArgument2: 2\r\n\r\n\0";
+//! let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+//! let key = SaoriCacheKey::from_request(&request).unwrap();
+//!
+//! let mut cache = SaoriResponseCache::new(16);
+//! if cache.get(&key).is_none() {
+//!     let mut response = SaoriResponse::from_request(&request);
+//!     response.set_result("3");
+//!     cache.insert(key.clone(), response);
+//! }
+//!
+//! // testing
+//! assert_eq!(cache.get(&key).unwrap().result(), "3");
+//! assert_eq!(cache.stats().hits(), 1);
+//! assert_eq!(cache.stats().misses(), 1);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::request::{SaoriCharset, SaoriRequest, SaoriVersion};
+use crate::response::SaoriResponse;
+
+/// SaoriResponseCacheのキー
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SaoriCacheKey {
+    function: String,
+    arguments: Vec<Option<String>>,
+    charset: SaoriCharset,
+    version: SaoriVersion,
+}
+
+/// SaoriResponseCacheの統計
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct SaoriCacheStats {
+    hits: u64,
+    misses: u64,
+    insertions: u64,
+    evictions: u64,
+    invalidations: u64,
+}
+
+/// 関数名と引数ごとにレスポンスを保持する
+#[derive(Debug)]
+pub struct SaoriResponseCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<SaoriCacheKey, (SaoriResponse, u64)>,
+    /// 最後に使われた時刻からキーへの対応。先頭が最も長く使われていないもの。
+    recency: BTreeMap<u64, SaoriCacheKey>,
+    stats: SaoriCacheStats,
+}
+
+impl SaoriCacheKey {
+    /// CharsetとバージョンはShift_JISとSAORI/1.0になる。変えるには`with_*`を使う。
+    pub fn new(function: impl Into<String>, arguments: Vec<String>) -> SaoriCacheKey {
+        SaoriCacheKey {
+            function: function.into(),
+            arguments: arguments.into_iter().map(Some).collect(),
+            charset: SaoriCharset::default(),
+            version: SaoriVersion::V1_0,
+        }
+    }
+
+    /// charsetを適用した自身を返す
+    pub fn with_charset(mut self, charset: SaoriCharset) -> SaoriCacheKey {
+        self.charset = charset;
+        self
+    }
+
+    /// versionを適用した自身を返す
+    pub fn with_version(mut self, version: SaoriVersion) -> SaoriCacheKey {
+        self.version = version;
+        self
+    }
+
+    /// Argument0を関数名、Argument1以降を引数として、リクエストのCharsetとバージョンも含めたキーを生成する
+    ///
+    /// 番号が飛ばされたArgument*は`None`として、空の値とは区別する。
+    /// Argument0がない場合は`None`を返す。
+    pub fn from_request(request: &SaoriRequest) -> Option<SaoriCacheKey> {
+        let function = request.argument(0)?;
+        let arguments = (1..request.arguments().len())
+            .map(|index| request.argument(index).map(|v| v.to_string()))
+            .collect();
+        Some(SaoriCacheKey {
+            function: function.to_string(),
+            arguments,
+            charset: request.charset().clone(),
+            version: request.version().clone(),
+        })
+    }
+
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    /// 引数。番号が飛ばされていたものは`None`。
+    pub fn arguments(&self) -> &[Option<String>] {
+        &self.arguments
+    }
+
+    pub fn charset(&self) -> &SaoriCharset {
+        &self.charset
+    }
+
+    pub fn version(&self) -> &SaoriVersion {
+        &self.version
+    }
+}
+
+impl SaoriCacheStats {
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn insertions(&self) -> u64 {
+        self.insertions
+    }
+
+    /// 容量を超えたために捨てた数
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// 明示的に無効にした数
+    pub fn invalidations(&self) -> u64 {
+        self.invalidations
+    }
+}
+
+impl SaoriResponseCache {
+    /// 最大で`capacity`個のレスポンスを保持する自身を生成する
+    pub fn new(capacity: usize) -> SaoriResponseCache {
+        SaoriResponseCache {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            stats: SaoriCacheStats::default(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn stats(&self) -> &SaoriCacheStats {
+        &self.stats
+    }
+
+    /// `key`のレスポンスを返す。見つかった場合は、最近使われたものとして扱う。
+    pub fn get(&mut self, key: &SaoriCacheKey) -> Option<&SaoriResponse> {
+        self.tick += 1;
+        match self.entries.get_mut(key) {
+            Some((response, used)) => {
+                if let Some(key) = self.recency.remove(used) {
+                    self.recency.insert(self.tick, key);
+                }
+                *used = self.tick;
+                self.stats.hits += 1;
+                Some(response)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// `key`のレスポンスを保持する。以前のものがあった場合はそれを返す。
+    pub fn insert(&mut self, key: SaoriCacheKey, response: SaoriResponse) -> Option<SaoriResponse> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        self.tick += 1;
+        self.stats.insertions += 1;
+        self.recency.insert(self.tick, key.clone());
+        let old = self
+            .entries
+            .insert(key, (response, self.tick))
+            .map(|(response, used)| {
+                self.recency.remove(&used);
+                response
+            });
+
+        while self.entries.len() > self.capacity {
+            self.evict_least_recently_used();
+        }
+
+        old
+    }
+
+    /// `key`のレスポンスを捨てて返す
+    pub fn invalidate(&mut self, key: &SaoriCacheKey) -> Option<SaoriResponse> {
+        let removed = self.entries.remove(key).map(|(response, used)| {
+            self.recency.remove(&used);
+            response
+        });
+        if removed.is_some() {
+            self.stats.invalidations += 1;
+        }
+        removed
+    }
+
+    /// 関数名が`function`のレスポンスをすべて捨て、その数を返す
+    pub fn invalidate_function(&mut self, function: &str) -> usize {
+        self.invalidate_if(|key, _| key.function == function)
+    }
+
+    /// `predicate`が`true`を返したレスポンスをすべて捨て、その数を返す
+    pub fn invalidate_if<F>(&mut self, mut predicate: F) -> usize
+    where
+        F: FnMut(&SaoriCacheKey, &SaoriResponse) -> bool,
+    {
+        let before = self.entries.len();
+        let recency = &mut self.recency;
+        self.entries.retain(|key, (response, used)| {
+            let keep = !predicate(key, response);
+            if !keep {
+                recency.remove(used);
+            }
+            keep
+        });
+        let removed = before - self.entries.len();
+        self.stats.invalidations += removed as u64;
+        removed
+    }
+
+    /// すべてのレスポンスを捨てる
+    pub fn clear(&mut self) {
+        self.stats.invalidations += self.entries.len() as u64;
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        if let Some((_, key)) = self.recency.pop_first() {
+            self.entries.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+
+    /// 古く使われたものから順に並べたキーとレスポンス
+    #[cfg(feature = "serde")]
+    fn entries_by_recency(&self) -> Vec<(&SaoriCacheKey, &SaoriResponse)> {
+        self.recency
+            .values()
+            .filter_map(|key| self.entries.get(key).map(|(response, _)| (key, response)))
+            .collect()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SaoriResponseCache {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("SaoriResponseCache", 2)?;
+        state.serialize_field("capacity", &self.capacity)?;
+        state.serialize_field("entries", &self.entries_by_recency())?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SaoriResponseCache {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Snapshot {
+            capacity: usize,
+            entries: Vec<(SaoriCacheKey, SaoriResponse)>,
+        }
+
+        let snapshot = Snapshot::deserialize(deserializer)?;
+        let mut cache = SaoriResponseCache::new(snapshot.capacity);
+        for (key, response) in snapshot.entries {
+            cache.insert(key, response);
+        }
+        cache.stats = SaoriCacheStats::default();
+        Ok(cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(function: &str) -> SaoriCacheKey {
+        SaoriCacheKey::new(function, vec!["1".to_string()])
+    }
+
+    fn response(result: &str) -> SaoriResponse {
+        let mut response = SaoriResponse::new_bad_request();
+        response.set_status(crate::response::SaoriStatus::OK);
+        response.set_result(result.to_string());
+        response
+    }
+
+    mod saori_cache_key {
+        use super::*;
+
+        mod from_request {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: add\r\nArgument1: 1\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriCacheKey::from_request(&request).unwrap();
+                assert_eq!(
+                    result,
+                    SaoriCacheKey::new("add", vec!["1".to_string()])
+                        .with_charset(SaoriCharset::UTF8)
+                );
+            }
+
+            #[test]
+            fn distinguish_skipped_argument_from_empty() {
+                let skipped_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: add\r\nArgument2: 1\r\n\r\n\0";
+                let empty_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: add\r\nArgument1: \r\nArgument2: 1\r\n\r\n\0";
+                let skipped = SaoriRequest::new(skipped_raw.as_bytes()).unwrap();
+                let empty = SaoriRequest::new(empty_raw.as_bytes()).unwrap();
+                let skipped = SaoriCacheKey::from_request(&skipped).unwrap();
+                let empty = SaoriCacheKey::from_request(&empty).unwrap();
+                assert_eq!(skipped.arguments(), &[None, Some("1".to_string())]);
+                assert_eq!(
+                    empty,
+                    SaoriCacheKey::new("add", vec![String::new(), "1".to_string()])
+                        .with_charset(SaoriCharset::UTF8)
+                );
+                assert_ne!(skipped, empty);
+            }
+
+            #[test]
+            fn distinguish_charset() {
+                let sjis_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\nArgument0: add\r\nArgument1: 1\r\n\r\n\0";
+                let utf8_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: add\r\nArgument1: 1\r\n\r\n\0";
+                let sjis = SaoriRequest::new(sjis_raw.as_bytes()).unwrap();
+                let utf8 = SaoriRequest::new(utf8_raw.as_bytes()).unwrap();
+                let sjis_key = SaoriCacheKey::from_request(&sjis).unwrap();
+                let utf8_key = SaoriCacheKey::from_request(&utf8).unwrap();
+                assert_eq!(sjis_key.charset(), &SaoriCharset::ShiftJIS);
+                assert_eq!(
+                    utf8_key,
+                    SaoriCacheKey::new("add", vec!["1".to_string()])
+                        .with_charset(SaoriCharset::UTF8)
+                );
+
+                let mut cache = SaoriResponseCache::new(4);
+                cache.insert(
+                    sjis_key,
+                    SaoriResponse::ok_with(&sjis, "1", Vec::<String>::new()),
+                );
+                assert!(cache.get(&utf8_key).is_none());
+            }
+
+            #[test]
+            fn none_when_no_arguments() {
+                let request_raw = "GET Version SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                assert!(SaoriCacheKey::from_request(&request).is_none());
+            }
+        }
+    }
+
+    mod saori_response_cache {
+        use super::*;
+
+        mod insert {
+            use super::*;
+
+            #[test]
+            fn evict_least_recently_used() {
+                let mut cache = SaoriResponseCache::new(2);
+                cache.insert(key("a"), response("a"));
+                cache.insert(key("b"), response("b"));
+                assert!(cache.get(&key("a")).is_some());
+                cache.insert(key("c"), response("c"));
+                assert_eq!(cache.len(), 2);
+                assert!(cache.get(&key("b")).is_none());
+                assert!(cache.get(&key("a")).is_some());
+                assert!(cache.get(&key("c")).is_some());
+                assert_eq!(cache.stats().evictions(), 1);
+            }
+
+            #[test]
+            fn evict_after_replace_and_invalidate() {
+                let mut cache = SaoriResponseCache::new(2);
+                cache.insert(key("a"), response("a"));
+                cache.insert(key("b"), response("b"));
+                cache.insert(key("a"), response("a2"));
+                cache.invalidate(&key("b"));
+                cache.insert(key("c"), response("c"));
+                cache.insert(key("d"), response("d"));
+                assert_eq!(cache.len(), 2);
+                assert!(cache.get(&key("a")).is_none());
+                assert!(cache.get(&key("c")).is_some());
+                assert!(cache.get(&key("d")).is_some());
+                assert_eq!(cache.stats().evictions(), 1);
+            }
+
+            #[test]
+            fn nothing_when_zero_capacity() {
+                let mut cache = SaoriResponseCache::new(0);
+                cache.insert(key("a"), response("a"));
+                assert!(cache.is_empty());
+            }
+        }
+
+        mod invalidate {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut cache = SaoriResponseCache::new(4);
+                cache.insert(key("a"), response("a"));
+                cache.insert(SaoriCacheKey::new("a", vec![]), response("a2"));
+                cache.insert(key("b"), response("b"));
+                assert_eq!(cache.invalidate(&key("b")).unwrap().result(), "b");
+                assert_eq!(cache.invalidate_function("a"), 2);
+                assert!(cache.is_empty());
+                assert_eq!(cache.stats().invalidations(), 3);
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        mod serde {
+            use super::*;
+
+            #[test]
+            fn round_trip_keeps_recency() {
+                let mut cache = SaoriResponseCache::new(2);
+                cache.insert(key("a"), response("a"));
+                cache.insert(key("b"), response("b"));
+                cache.get(&key("a"));
+
+                let json = serde_json::to_string(&cache).unwrap();
+                let mut result: SaoriResponseCache = serde_json::from_str(&json).unwrap();
+                assert_eq!(result.capacity(), 2);
+                assert_eq!(result.stats(), &SaoriCacheStats::default());
+
+                result.insert(key("c"), response("c"));
+                assert!(result.get(&key("b")).is_none());
+                assert_eq!(result.get(&key("a")).unwrap().result(), "a");
+            }
+        }
+    }
+}
@@ -0,0 +1,587 @@
+//! リクエストやレスポンスの処理中に見つかった問題をまとめる
+//!
+//! # Examples
+//!
+//! ```
+//! use saori_interface_rs::*;
+//!
+//! let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+//! let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+//!
+//! let mut case = SaoriResponse::from_request(&request);
+//! case.set_result("\u{1F600}".to_string());
+//! let diagnostics = case.diagnostics();
+//!
+//! // testing
+//! assert!(diagnostics.has_errors());
+//! assert_eq!(diagnostics.max_severity(), Some(SaoriSeverity::Error));
+//! ```
+
+use crate::request::{
+    SaoriArgumentParseError, SaoriParseWarning, SaoriRequestArgumentError, SaoriRequestBuildError,
+    SaoriRequestCharsetError, SaoriRequestError, SaoriRequestFramingError, SaoriRequestHeaderError,
+    SaoriRequestLimitError, SaoriRequestVersionLineError,
+};
+use crate::response::{
+    SaoriResponseBuildError, SaoriResponseError, SaoriResponseField, SaoriResponseParseError,
+};
+
+/// 問題の重大度
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum SaoriSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// 問題の発生箇所
+#[derive(PartialEq, Debug, Clone)]
+pub enum SaoriLocation {
+    /// リクエストやレスポンスの行(0始まり)
+    Line(usize),
+    /// レスポンスのヘッダ
+    Field(SaoriResponseField),
+    /// リクエストのバイト列の位置(先頭からのバイト数)
+    Offset(usize),
+}
+
+/// 見つかった問題ひとつ
+#[derive(PartialEq, Debug, Clone)]
+pub struct SaoriDiagnostic {
+    severity: SaoriSeverity,
+    code: u32,
+    name: &'static str,
+    location: Option<SaoriLocation>,
+    message: String,
+}
+
+/// 見つかった問題の一覧
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SaoriDiagnostics {
+    items: Vec<SaoriDiagnostic>,
+}
+
+impl SaoriDiagnostic {
+    pub fn new(
+        severity: SaoriSeverity,
+        code: u32,
+        name: &'static str,
+        message: String,
+    ) -> SaoriDiagnostic {
+        SaoriDiagnostic {
+            severity,
+            code,
+            name,
+            location: None,
+            message,
+        }
+    }
+
+    /// 発生箇所を設定した自身を返す
+    pub fn with_location(mut self, location: SaoriLocation) -> SaoriDiagnostic {
+        self.location = Some(location);
+        self
+    }
+
+    pub fn severity(&self) -> SaoriSeverity {
+        self.severity
+    }
+
+    /// 問題の番号(エラーの`code()`と同じ)
+    pub fn code(&self) -> u32 {
+        self.code
+    }
+
+    /// 機械的に判別するための識別子
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn location(&self) -> Option<&SaoriLocation> {
+        self.location.as_ref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl SaoriDiagnostics {
+    pub fn new() -> SaoriDiagnostics {
+        SaoriDiagnostics::default()
+    }
+
+    pub fn push(&mut self, diagnostic: SaoriDiagnostic) {
+        self.items.push(diagnostic);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, SaoriDiagnostic> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// `severity`の重大度の問題のみを返す
+    pub fn with_severity(
+        &self,
+        severity: SaoriSeverity,
+    ) -> impl Iterator<Item = &SaoriDiagnostic> + '_ {
+        self.items.iter().filter(move |v| v.severity == severity)
+    }
+
+    /// Errorの問題があるかどうか
+    pub fn has_errors(&self) -> bool {
+        self.items
+            .iter()
+            .any(|v| v.severity == SaoriSeverity::Error)
+    }
+
+    /// 最も重い重大度を返す
+    pub fn max_severity(&self) -> Option<SaoriSeverity> {
+        self.items.iter().map(|v| v.severity).max()
+    }
+}
+
+impl IntoIterator for SaoriDiagnostics {
+    type Item = SaoriDiagnostic;
+    type IntoIter = std::vec::IntoIter<SaoriDiagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SaoriDiagnostics {
+    type Item = &'a SaoriDiagnostic;
+    type IntoIter = std::slice::Iter<'a, SaoriDiagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl FromIterator<SaoriDiagnostic> for SaoriDiagnostics {
+    fn from_iter<T: IntoIterator<Item = SaoriDiagnostic>>(iter: T) -> Self {
+        SaoriDiagnostics {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<SaoriDiagnostic> for SaoriDiagnostics {
+    fn extend<T: IntoIterator<Item = SaoriDiagnostic>>(&mut self, iter: T) {
+        self.items.extend(iter);
+    }
+}
+
+impl From<&SaoriRequestError> for SaoriDiagnostic {
+    fn from(e: &SaoriRequestError) -> SaoriDiagnostic {
+        let (name, message) = match e {
+            SaoriRequestError::Charset(SaoriRequestCharsetError::DecodeFailed(offset, context)) => {
+                (
+                    "request.charset.decode_failed",
+                    format!(
+                        "request could not be decoded with its charset at byte {} ({})",
+                        offset, context
+                    ),
+                )
+            }
+            SaoriRequestError::Charset(SaoriRequestCharsetError::UnsupportedCharset) => (
+                "request.charset.unsupported",
+                "request charset is not supported".to_string(),
+            ),
+            SaoriRequestError::Charset(SaoriRequestCharsetError::EncodeFailed) => (
+                "request.charset.encode_failed",
+                "request could not be encoded with its charset".to_string(),
+            ),
+            SaoriRequestError::VersionLine(SaoriRequestVersionLineError::EmptyRequest) => {
+                ("request.version_line.empty", "request is empty".to_string())
+            }
+            SaoriRequestError::VersionLine(SaoriRequestVersionLineError::NoVersion) => (
+                "request.version_line.no_version",
+                "request line has no valid version".to_string(),
+            ),
+            SaoriRequestError::VersionLine(SaoriRequestVersionLineError::NoCommand) => (
+                "request.version_line.no_command",
+                "request line has no valid command".to_string(),
+            ),
+            SaoriRequestError::Argument(SaoriRequestArgumentError::InvalidSeparator(_, line)) => (
+                "request.argument.invalid_separator",
+                format!("argument header '{}' has no ': ' separator", line),
+            ),
+            SaoriRequestError::Argument(SaoriRequestArgumentError::NoIndex(_, line)) => (
+                "request.argument.no_index",
+                format!("argument header '{}' has no valid index", line),
+            ),
+            SaoriRequestError::Header(SaoriRequestHeaderError::Unknown(_, header)) => (
+                "request.header.unknown",
+                format!("unknown header '{}'", header),
+            ),
+            SaoriRequestError::Header(SaoriRequestHeaderError::Duplicate(_, header)) => (
+                "request.header.duplicate",
+                format!("duplicate header '{}'", header),
+            ),
+            SaoriRequestError::Header(SaoriRequestHeaderError::LineBreak(_, header)) => (
+                "request.header.line_break",
+                format!("header '{}' contains a line break or NUL", header),
+            ),
+            SaoriRequestError::Framing(SaoriRequestFramingError::BareLineFeed(_)) => (
+                "request.framing.bare_line_feed",
+                "request has a line ending that is not CRLF".to_string(),
+            ),
+            SaoriRequestError::Framing(SaoriRequestFramingError::MissingNul) => (
+                "request.framing.missing_nul",
+                "request does not end with NUL".to_string(),
+            ),
+            SaoriRequestError::Framing(SaoriRequestFramingError::ControlCharacter(_, c)) => (
+                "request.framing.control_character",
+                format!("request contains control character U+{:04X}", *c as u32),
+            ),
+            SaoriRequestError::Limit(SaoriRequestLimitError::TooLarge(size)) => (
+                "request.limit.too_large",
+                format!("request is too large ({} bytes)", size),
+            ),
+            SaoriRequestError::Limit(SaoriRequestLimitError::TooManyArguments(index)) => (
+                "request.limit.too_many_arguments",
+                format!("Argument{} exceeds the argument limit", index),
+            ),
+            SaoriRequestError::Limit(SaoriRequestLimitError::LineTooLong(_, length)) => (
+                "request.limit.line_too_long",
+                format!("line is too long ({} bytes)", length),
+            ),
+        };
+
+        let diagnostic = SaoriDiagnostic::new(SaoriSeverity::Error, e.code(), name, message);
+        match (e.line(), e) {
+            (Some(line), _) => diagnostic.with_location(SaoriLocation::Line(line)),
+            (
+                None,
+                SaoriRequestError::Charset(SaoriRequestCharsetError::DecodeFailed(offset, _)),
+            ) => diagnostic.with_location(SaoriLocation::Offset(*offset)),
+            (None, _) => diagnostic,
+        }
+    }
+}
+
+impl From<&SaoriArgumentParseError> for SaoriDiagnostic {
+    fn from(e: &SaoriArgumentParseError) -> SaoriDiagnostic {
+        match e {
+            SaoriArgumentParseError::Missing(index) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                e.code(),
+                "argument.missing",
+                format!("Argument{} is missing", index),
+            ),
+            SaoriArgumentParseError::Invalid(index, value) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                e.code(),
+                "argument.invalid",
+                format!("Argument{} '{}' could not be converted", index, value),
+            ),
+        }
+    }
+}
+
+impl From<&SaoriParseWarning> for SaoriDiagnostic {
+    fn from(w: &SaoriParseWarning) -> SaoriDiagnostic {
+        let (name, message) = match w {
+            SaoriParseWarning::UnknownSecurityLevel(_, value) => (
+                "request.warning.unknown_security_level",
+                format!("unknown SecurityLevel '{}'", value),
+            ),
+            SaoriParseWarning::MalformedLine(_, line) => (
+                "request.warning.malformed_line",
+                format!("line '{}' is not a header and was ignored", line),
+            ),
+            SaoriParseWarning::HeaderCase(_, name) => (
+                "request.warning.header_case",
+                format!(
+                    "header '{}' differs from the specification only in case",
+                    name
+                ),
+            ),
+            SaoriParseWarning::OrphanContinuation(_, line) => (
+                "request.warning.orphan_continuation",
+                format!(
+                    "continuation line '{}' has no header before it and was ignored",
+                    line
+                ),
+            ),
+            SaoriParseWarning::CharsetCorrected(_, declared, detected) => (
+                "request.warning.charset_corrected",
+                format!(
+                    "declared charset '{}' does not match the content; read as '{}'",
+                    declared, detected
+                ),
+            ),
+        };
+
+        SaoriDiagnostic::new(SaoriSeverity::Warning, w.code(), name, message)
+            .with_location(SaoriLocation::Line(w.line()))
+    }
+}
+
+impl From<&SaoriResponseError> for SaoriDiagnostic {
+    fn from(e: &SaoriResponseError) -> SaoriDiagnostic {
+        let code = e.code();
+        match e {
+            SaoriResponseError::EncodeFailed => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.encode_failed",
+                "response could not be encoded".to_string(),
+            ),
+            SaoriResponseError::Unencodable(e) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.unencodable",
+                format!(
+                    "'{}' (U+{:04X}) at byte {} cannot be encoded in {}",
+                    e.character(),
+                    e.character() as u32,
+                    e.offset(),
+                    e.charset().to_str()
+                ),
+            )
+            .with_location(SaoriLocation::Field(e.field().clone())),
+            SaoriResponseError::InvalidHeader(name) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.invalid_header",
+                format!("header '{}' has an invalid name or value", name),
+            )
+            .with_location(SaoriLocation::Field(SaoriResponseField::Header(
+                name.clone(),
+            ))),
+            SaoriResponseError::InvalidStatus(status) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.invalid_status",
+                format!("status code {} is not a three-digit number", status),
+            ),
+            SaoriResponseError::InvalidStatusReason(reason) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.invalid_status_reason",
+                format!(
+                    "status reason '{}' contains a control character",
+                    reason.escape_debug()
+                ),
+            ),
+            SaoriResponseError::LineBreak(field) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.line_break",
+                "value contains a line break or NUL".to_string(),
+            )
+            .with_location(SaoriLocation::Field(field.clone())),
+            SaoriResponseError::WriteFailed(kind) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.write_failed",
+                format!("response could not be written ({:?})", kind),
+            ),
+            SaoriResponseError::TooLarge(size) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.too_large",
+                format!("encoded response is too large ({} bytes)", size),
+            ),
+        }
+    }
+}
+
+impl From<&SaoriResponseParseError> for SaoriDiagnostic {
+    fn from(e: &SaoriResponseParseError) -> SaoriDiagnostic {
+        let (name, message) = match e {
+            SaoriResponseParseError::Charset(SaoriRequestCharsetError::UnsupportedCharset) => (
+                "response.parse.unsupported_charset",
+                "response charset is not supported".to_string(),
+            ),
+            SaoriResponseParseError::Charset(_) => (
+                "response.parse.decode_failed",
+                "response could not be decoded with its charset".to_string(),
+            ),
+            SaoriResponseParseError::EmptyResponse => {
+                ("response.parse.empty", "response is empty".to_string())
+            }
+            SaoriResponseParseError::InvalidStatusLine(line) => (
+                "response.parse.invalid_status_line",
+                format!("status line '{}' is not valid", line),
+            ),
+            SaoriResponseParseError::InvalidHeader(_, line) => (
+                "response.parse.invalid_header",
+                format!("header '{}' has no ': ' separator", line),
+            ),
+            SaoriResponseParseError::ValueIndexTooLarge(_, index) => (
+                "response.parse.value_index_too_large",
+                format!("Value{} is too large an index", index),
+            ),
+        };
+        let diagnostic = SaoriDiagnostic::new(SaoriSeverity::Error, e.code(), name, message);
+        match e.line() {
+            Some(line) => diagnostic.with_location(SaoriLocation::Line(line)),
+            None => diagnostic,
+        }
+    }
+}
+
+impl From<&SaoriRequestBuildError> for SaoriDiagnostic {
+    fn from(e: &SaoriRequestBuildError) -> SaoriDiagnostic {
+        let (name, message) = match e {
+            SaoriRequestBuildError::LineBreak(header) => (
+                "request.build.line_break",
+                format!("header '{}' contains a line break or NUL", header),
+            ),
+            SaoriRequestBuildError::InvalidHeader(header) => (
+                "request.build.invalid_header",
+                format!("'{}' cannot be used as a header name", header),
+            ),
+        };
+        SaoriDiagnostic::new(SaoriSeverity::Error, e.code(), name, message)
+    }
+}
+
+impl From<&SaoriResponseBuildError> for SaoriDiagnostic {
+    fn from(e: &SaoriResponseBuildError) -> SaoriDiagnostic {
+        let code = e.code();
+        match e {
+            SaoriResponseBuildError::Unencodable(inner) => {
+                let diagnostic =
+                    SaoriDiagnostic::from(&SaoriResponseError::Unencodable(inner.clone()));
+                SaoriDiagnostic { code, ..diagnostic }
+            }
+            SaoriResponseBuildError::LineBreak(field) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.build.line_break",
+                "value contains a line break".to_string(),
+            )
+            .with_location(SaoriLocation::Field(field.clone())),
+            SaoriResponseBuildError::TooLarge(size) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.build.too_large",
+                format!("encoded response is too large ({} bytes)", size),
+            ),
+            SaoriResponseBuildError::MissingResult => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.build.missing_result",
+                "status is OK but there is no Result or Value".to_string(),
+            ),
+            SaoriResponseBuildError::InvalidStatus(status) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.build.invalid_status",
+                format!(
+                    "status '{}' cannot be written",
+                    status.to_string().escape_debug()
+                ),
+            ),
+            SaoriResponseBuildError::EncodeFailed(e) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.build.encode_failed",
+                format!(
+                    "response could not be encoded: {}",
+                    SaoriDiagnostic::from(e).message()
+                ),
+            ),
+            SaoriResponseBuildError::InvalidHeader(name) => SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                code,
+                "response.build.invalid_header",
+                format!("'{}' cannot be used as a header name", name),
+            )
+            .with_location(SaoriLocation::Field(SaoriResponseField::Header(
+                name.clone(),
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod saori_diagnostics {
+        use super::*;
+
+        fn diagnostic(severity: SaoriSeverity) -> SaoriDiagnostic {
+            SaoriDiagnostic::new(severity, 0, "test", String::new())
+        }
+
+        mod has_errors {
+            use super::*;
+
+            #[test]
+            fn true_when_error_exists() {
+                let case: SaoriDiagnostics = vec![
+                    diagnostic(SaoriSeverity::Info),
+                    diagnostic(SaoriSeverity::Error),
+                ]
+                .into_iter()
+                .collect();
+                assert!(case.has_errors());
+            }
+
+            #[test]
+            fn false_when_only_warnings() {
+                let case: SaoriDiagnostics = vec![diagnostic(SaoriSeverity::Warning)]
+                    .into_iter()
+                    .collect();
+                assert!(!case.has_errors());
+            }
+        }
+
+        mod max_severity {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriDiagnostics::new();
+                assert_eq!(case.max_severity(), None);
+                case.push(diagnostic(SaoriSeverity::Info));
+                case.push(diagnostic(SaoriSeverity::Warning));
+                assert_eq!(case.max_severity(), Some(SaoriSeverity::Warning));
+                assert_eq!(case.with_severity(SaoriSeverity::Info).count(), 1);
+            }
+        }
+    }
+
+    mod from_request_error {
+        use super::*;
+
+        #[test]
+        fn checking_value() {
+            let case = SaoriRequestError::Argument(SaoriRequestArgumentError::NoIndex(
+                2,
+                "Argumentx: a".to_string(),
+            ));
+            let result = SaoriDiagnostic::from(&case);
+            assert_eq!(result.severity(), SaoriSeverity::Error);
+            assert_eq!(result.code(), 1302);
+            assert_eq!(result.name(), "request.argument.no_index");
+            assert_eq!(result.location(), Some(&SaoriLocation::Line(2)));
+        }
+
+        #[test]
+        fn offset_when_decode_failed() {
+            let case = SaoriRequestError::Charset(SaoriRequestCharsetError::DecodeFailed(
+                5,
+                "41 [FF] 42".to_string(),
+            ));
+            let result = SaoriDiagnostic::from(&case);
+            assert_eq!(result.code(), 1101);
+            assert_eq!(result.location(), Some(&SaoriLocation::Offset(5)));
+            assert!(result.message().contains("41 [FF] 42"));
+        }
+    }
+}
@@ -17,7 +17,7 @@
 //! let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
 //!
 //! let mut case = SaoriResponse::from_request(&request);
-//! case.set_result("1".to_string());
+//! case.set_result("1");
 //! case.set_values(vec!["aaa".to_string(), "bbb".to_string()]);
 //! let result = case.to_encoded_bytes().unwrap();
 //!
@@ -34,8 +34,15 @@
 //! [`SaoriResponse::new_bad_request`]: crate::response::SaoriResponse::new_bad_request
 //! [`SaoriResponse::to_encoded_bytes`]: crate::response::SaoriResponse::to_encoded_bytes
 
+pub mod cache;
+pub mod diagnostics;
+pub mod module_info;
+mod pretty;
 pub mod request;
 pub mod response;
 
+pub use cache::*;
+pub use diagnostics::*;
+pub use module_info::*;
 pub use request::*;
 pub use response::*;
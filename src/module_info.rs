@@ -0,0 +1,119 @@
+//! GET Version に答えるための、モジュールの情報
+//!
+//! # Examples
+//!
+//! ```
+//! use saori_interface_rs::*;
+//!
+//! const INFO: SaoriModuleInfo<'static> =
+//!     SaoriModuleInfo::new("calc", "1.2.0").with_author("tukinami");
+//!
+//! let request_raw = "GET Version SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+//! let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+//! let response = INFO.to_response(&request).unwrap();
+//!
+//! // testing
+//! assert_eq!(
+//!     response.to_string(),
+//!     "SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: 1.2.0\r\nX-SAORI-Module: calc\r\nX-SAORI-Author: tukinami\r\n\r\n\0"
+//! );
+//! ```
+
+use crate::request::SaoriRequest;
+use crate::response::{SaoriResponse, SaoriResponseError};
+
+/// モジュールの名前、バージョン、作者
+///
+/// 定数として置けるように、値は`&str`で持つ。
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct SaoriModuleInfo<'a> {
+    name: &'a str,
+    version: &'a str,
+    author: Option<&'a str>,
+}
+
+impl<'a> SaoriModuleInfo<'a> {
+    pub const fn new(name: &'a str, version: &'a str) -> SaoriModuleInfo<'a> {
+        SaoriModuleInfo {
+            name,
+            version,
+            author: None,
+        }
+    }
+
+    /// 作者を設定した自身を返す
+    pub const fn with_author(mut self, author: &'a str) -> SaoriModuleInfo<'a> {
+        self.author = Some(author);
+        self
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn version(&self) -> &'a str {
+        self.version
+    }
+
+    pub fn author(&self) -> Option<&'a str> {
+        self.author
+    }
+
+    /// GET Version へのレスポンスを返す
+    ///
+    /// Resultにバージョンを、`X-SAORI-Module`に名前を、`X-SAORI-Author`に作者を入れる。
+    /// いずれかに改行が含まれる場合は [`SaoriResponseError::InvalidHeader`] を返す。
+    pub fn to_response(&self, request: &SaoriRequest) -> Result<SaoriResponse, SaoriResponseError> {
+        if self.version.contains(['\r', '\n']) {
+            return Err(SaoriResponseError::InvalidHeader("Result".to_string()));
+        }
+
+        let mut response = SaoriResponse::version_response(request, self.version);
+        response.set_x_saori_header("Module", self.name)?;
+        if let Some(author) = self.author {
+            response.set_x_saori_header("Author", author)?;
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod saori_module_info {
+        use super::*;
+
+        mod to_response {
+            use super::*;
+
+            #[test]
+            fn checking_value_without_author() {
+                let request_raw = "GET Version SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriModuleInfo::new("calc", "1.0")
+                    .to_response(&request)
+                    .unwrap();
+                assert_eq!(
+                    result.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nResult: 1.0\r\nX-SAORI-Module: calc\r\n\r\n\0"
+                );
+            }
+
+            #[test]
+            fn failed_when_line_break() {
+                let request_raw = "GET Version SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriModuleInfo::new("calc", "1.0")
+                    .with_author("a\nb")
+                    .to_response(&request);
+                assert_eq!(
+                    result,
+                    Err(SaoriResponseError::InvalidHeader(
+                        "X-SAORI-Author".to_string()
+                    ))
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! 人が読むための、リクエストやレスポンスの整形
+
+/// `title`の下に、ラベルをそろえた`rows`を並べる
+///
+/// 値の改行や制御文字はエスケープして表示する。
+pub(crate) fn format_rows(title: &str, rows: &[(String, String)]) -> String {
+    let width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut result = String::from(title);
+    for (label, value) in rows {
+        result.push_str("\n  ");
+        result.push_str(label);
+        for _ in label.chars().count()..width {
+            result.push(' ');
+        }
+        result.push_str(" : ");
+        for c in value.chars() {
+            if c.is_control() {
+                result.extend(c.escape_debug());
+            } else {
+                result.push(c);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod format_rows {
+        use super::*;
+
+        #[test]
+        fn checking_value() {
+            let rows = vec![
+                ("A".to_string(), "1".to_string()),
+                ("Long".to_string(), "a\r\nb".to_string()),
+            ];
+            let result = format_rows("Title", &rows);
+            assert_eq!(result, "Title\n  A    : 1\n  Long : a\\r\\nb");
+        }
+    }
+}
@@ -17,10 +17,13 @@
 //! assert!(request.sender().is_none());
 //! ```
 
-use encoding::{
-    all::{EUC_JP, ISO_2022_JP, UTF_8, WINDOWS_31J},
-    types::Encoding,
-    DecoderTrap,
+use std::{borrow::Cow, collections::HashSet, fmt::Display, str::FromStr};
+
+use crate::diagnostics::{SaoriDiagnostic, SaoriDiagnostics, SaoriLocation, SaoriSeverity};
+
+use encoding_rs::{
+    DecoderResult, Encoder, EncoderResult, Encoding, EUC_JP, ISO_2022_JP, SHIFT_JIS, UTF_16BE,
+    UTF_16LE, UTF_8, WINDOWS_1252,
 };
 
 const SAORI_PREFIX_CHARSET: &str = "Charset: ";
@@ -29,6 +32,12 @@ const SAORI_COMMAND_EXECUTE: &str = "EXECUTE ";
 const SAORI_PREFIX_SECULITY_LEVEL: &str = "SecurityLevel: ";
 const SAORI_PREFIX_ARGUMENT: &str = "Argument";
 const SAORI_PREFIX_SENDER: &str = "Sender: ";
+const SAORI_VERSION_PREFIX: &str = "SAORI/";
+pub(crate) const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16BE_BOM: &[u8] = b"\xFE\xFF";
+/// デコードに失敗した箇所の前後に含めるバイト数
+const DECODE_CONTEXT_BYTES: usize = 4;
 
 /// SAORIのリクエストを処理中のエラー
 #[derive(Debug, PartialEq)]
@@ -36,13 +45,20 @@ pub enum SaoriRequestError {
     Charset(SaoriRequestCharsetError),
     VersionLine(SaoriRequestVersionLineError),
     Argument(SaoriRequestArgumentError),
+    Header(SaoriRequestHeaderError),
+    Framing(SaoriRequestFramingError),
+    Limit(SaoriRequestLimitError),
 }
 
 /// SAORIのリクエストを処理中のエラー: Charset関連
 #[derive(Debug, PartialEq)]
 pub enum SaoriRequestCharsetError {
-    DecodeFailed,
+    /// 読めなかった位置(先頭からのバイト数)と、その前後のバイトの16進表記を持つ
+    ///
+    /// 16進表記では、読めなかった位置のバイトを`[]`で囲む。
+    DecodeFailed(usize, String),
     UnsupportedCharset,
+    EncodeFailed,
 }
 
 /// SAORIのリクエストを処理中のエラー: Version関連
@@ -54,10 +70,129 @@ pub enum SaoriRequestVersionLineError {
 }
 
 /// SAORIのリクエストを処理中のエラー: Argument関連
+///
+/// 行(0始まり、リクエスト行が0)と、その行の内容を持つ。
 #[derive(Debug, PartialEq)]
 pub enum SaoriRequestArgumentError {
-    InvalidSeparator,
-    NoIndex,
+    InvalidSeparator(usize, String),
+    NoIndex(usize, String),
+}
+
+/// SAORIのリクエストを処理中のエラー: ヘッダ関連(strictのときなど)
+///
+/// 行(0始まり、リクエスト行が0)と、ヘッダの名前を持つ。
+#[derive(Debug, PartialEq)]
+pub enum SaoriRequestHeaderError {
+    /// 仕様にないヘッダがあった
+    Unknown(usize, String),
+    /// 同じヘッダが複数あった(2つ目の行を持つ)
+    Duplicate(usize, String),
+    /// 書き出すときに、値に改行か`\0`が含まれていた(行は書き出したときのもの)
+    LineBreak(usize, String),
+}
+
+/// SAORIのリクエストを処理中のエラー: 改行や終端関連
+#[derive(Debug, PartialEq)]
+pub enum SaoriRequestFramingError {
+    /// CRLFでない改行があった(require_crlfのときのみ、その行を持つ)
+    BareLineFeed(usize),
+    /// 末尾が`\0`でなかった(require_nulのときのみ)
+    MissingNul,
+    /// 途中に`\0`や制御文字があった(SaoriControlCharacterPolicy::Rejectのときのみ、行と文字を持つ)
+    ControlCharacter(usize, char),
+}
+
+/// SAORIのリクエストを処理中のエラー: SaoriLimitsを超えた
+#[derive(Debug, PartialEq)]
+pub enum SaoriRequestLimitError {
+    /// リクエスト全体のバイト数が多すぎる(そのバイト数を持つ)
+    TooLarge(usize),
+    /// Argument*の数が多すぎる(超えた番号を持つ)
+    TooManyArguments(usize),
+    /// 行が長すぎる(行と、そのバイト数を持つ)
+    LineTooLong(usize, usize),
+}
+
+/// Argument*を型に変換するときのエラー
+#[derive(Debug, PartialEq)]
+pub enum SaoriArgumentParseError {
+    /// `index`のArgument*がなかった
+    Missing(usize),
+    /// `index`のArgument*を変換できなかった(値を持つ)
+    Invalid(usize, String),
+}
+
+/// 読み取りは続けられたが、仕様から外れていた箇所
+///
+/// 最初の値は、問題のあった行(0始まり、リクエスト行が0)。
+#[derive(Debug, PartialEq, Clone)]
+pub enum SaoriParseWarning {
+    /// SecurityLevelの値が仕様にないもので、SaoriSecurityLevel::Otherとして受け入れた
+    UnknownSecurityLevel(usize, String),
+    /// `名前: 値`の形になっていない行で、無視した
+    MalformedLine(usize, String),
+    /// 仕様にあるヘッダ名の大文字と小文字が違っていて、仕様にないヘッダとして扱った
+    HeaderCase(usize, String),
+    /// 内容が宣言されたCharset(2番目)として明らかに読めず、推定したCharset(3番目)で読み取った
+    CharsetCorrected(usize, SaoriCharset, SaoriCharset),
+    /// 前にヘッダがない続きの行で、無視した(unfold_linesのときのみ)
+    OrphanContinuation(usize, String),
+}
+
+/// 同じヘッダが複数あったときの扱い
+///
+/// Charset、Sender、SecurityLevel、Argument*のそれぞれに適用する。
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum SaoriDuplicateHeaderPolicy {
+    /// 後のものを使う
+    #[default]
+    LastWins,
+    /// 最初のものを使う
+    FirstWins,
+    /// エラーにする
+    Error,
+}
+
+/// リクエストを読み取るときの上限
+///
+/// 既定では上限はない。常駐するホストで、大きすぎるリクエストによる確保を防ぐために使う。
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct SaoriLimits {
+    max_bytes: Option<usize>,
+    max_arguments: Option<usize>,
+    max_line_length: Option<usize>,
+}
+
+/// 途中の`\0`や、タブ以外のC0制御文字の扱い
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum SaoriControlCharacterPolicy {
+    /// そのまま受け入れる
+    #[default]
+    Allow,
+    /// エラーにする
+    Reject,
+    /// 取り除く
+    Strip,
+}
+
+/// SAORIのリクエストの読み取り方の設定
+///
+/// 既定では、LFのみの改行と、末尾の`\0`がないことを許す。
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SaoriParseOptions {
+    strict: bool,
+    duplicate_headers: SaoriDuplicateHeaderPolicy,
+    accept_unknown_command: bool,
+    accept_unknown_version: bool,
+    trim_whitespace: bool,
+    require_crlf: bool,
+    require_nul: bool,
+    keep_raw_bytes: bool,
+    default_charset: SaoriCharset,
+    limits: SaoriLimits,
+    control_characters: SaoriControlCharacterPolicy,
+    unfold_lines: bool,
+    detect_charset: bool,
 }
 
 /// SHIORIから来たSAORIのリクエストからを表す
@@ -68,29 +203,126 @@ pub struct SaoriRequest {
     version: SaoriVersion,
     security_level: Option<SaoriSecurityLevel>,
     arguments: Vec<String>,
+    arguments_present: Vec<bool>,
     sender: Option<String>,
+    extra_headers: Vec<(String, String)>,
+    raw_bytes: Option<Vec<u8>>,
+    framing: SaoriFraming,
+    warnings: Vec<SaoriParseWarning>,
 }
 
-/// SAORIのCharset
+/// 値を伏せたSaoriRequestを表示する
+///
+/// Argument*、Sender、仕様にないヘッダの値を`***`に置き換える(空の値はそのまま)。
+/// 受け取ったバイト列は含まない。
+pub struct SaoriRedactedRequest {
+    inner: SaoriRequest,
+}
+
+/// リクエストやレスポンスの改行の種類
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum SaoriLineEnding {
+    #[default]
+    CrLf,
+    Lf,
+    /// CRLFとLFが混ざっている
+    Mixed,
+}
+
+/// 送られてきたリクエストの、内容以外の形式
+///
+/// 既定値は、書き出すときの形式(CRLF、末尾に`\0`、BOMなし)。
+/// レスポンスを書き出す形式の指定にも使う。
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct SaoriFraming {
+    line_ending: SaoriLineEnding,
+    has_nul: bool,
+    has_bom: bool,
+}
+
+/// 送られてきたArgument*を、番号と値の組で返すイテレータ
+#[derive(Debug, Clone)]
+pub struct SaoriArgumentsIter<'a> {
+    inner: std::iter::Enumerate<
+        std::iter::Zip<std::slice::Iter<'a, String>, std::slice::Iter<'a, bool>>,
+    >,
+}
+
+/// 送られてきたArgument*を、番号と値の組で返す消費イテレータ
+#[derive(Debug)]
+pub struct SaoriArgumentsIntoIter {
+    inner:
+        std::iter::Enumerate<std::iter::Zip<std::vec::IntoIter<String>, std::vec::IntoIter<bool>>>,
+}
+
+/// `key=value`または`key: value`の形のArgument*を、送られてきた順に保持する
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SaoriNamedArguments<'a> {
+    entries: Vec<(&'a str, &'a str)>,
+}
+
+/// SaoriRequestを組み立てる
 #[derive(PartialEq, Debug, Clone)]
+pub struct SaoriRequestBuilder {
+    charset: SaoriCharset,
+    command: SaoriCommand,
+    version: SaoriVersion,
+    security_level: Option<SaoriSecurityLevel>,
+    arguments: Vec<String>,
+    sender: Option<String>,
+    extra_headers: Vec<(String, String)>,
+}
+
+/// SaoriRequestBuilderで組み立て中に見つかった問題
+#[derive(PartialEq, Debug)]
+pub enum SaoriRequestBuildError {
+    /// 値に改行か`\0`が含まれていた(ヘッダの名前を持つ。リクエスト行は`Command`か`Version`)
+    LineBreak(String),
+    /// 仕様にないヘッダの名前が不正か、仕様にあるヘッダと同じだった
+    InvalidHeader(String),
+}
+
+/// SAORIのCharset
+///
+/// 既定値は、Charsetヘッダがないときに仕様上使われるShift_JIS。
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SaoriCharset {
+    #[default]
     ShiftJIS,
     EucJP,
     UTF8,
     ISO2022JP,
+    /// 終端の`\0`も2バイトになる
+    UTF16LE,
+    /// 終端の`\0`も2バイトになる
+    UTF16BE,
+    /// US-ASCII
+    ASCII,
+    /// ISO-8859-1
+    Latin1,
+    /// 上記以外の、`encoding_rs`が対応しているもの(GBK、EUC-KRなど)
+    ///
+    /// 上記と同じエンコーディングは持たない。 [`SaoriCharset::try_from`] で生成する。
+    Other(#[cfg_attr(feature = "serde", serde(with = "serde_encoding"))] &'static Encoding),
 }
 
 /// SAORIのコマンド
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum SaoriCommand {
     Execute,
     GetVersion,
+    /// 仕様にないコマンド(accept_unknown_commandのときのみ)
+    Other(String),
 }
 
 /// SAORIのバージョン
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SaoriVersion {
     V1_0,
+    /// 対応していないバージョン(accept_unknown_versionのときのみ)
+    Other(String),
 }
 
 /// SAORIのSecurityLevel
@@ -98,6 +330,8 @@ pub enum SaoriVersion {
 pub enum SaoriSecurityLevel {
     Local,
     External,
+    /// 仕様にないSecurityLevel(値をそのまま持つ)
+    Other(String),
 }
 
 impl From<SaoriRequestCharsetError> for SaoriRequestError {
@@ -118,293 +352,3526 @@ impl From<SaoriRequestArgumentError> for SaoriRequestError {
     }
 }
 
-impl SaoriRequest {
-    pub fn new(bytes: &[u8]) -> Result<SaoriRequest, SaoriRequestError> {
-        let (body, charset) = SaoriRequest::read_contents_and_charset(bytes)?;
+impl From<SaoriRequestHeaderError> for SaoriRequestError {
+    fn from(e: SaoriRequestHeaderError) -> SaoriRequestError {
+        SaoriRequestError::Header(e)
+    }
+}
 
-        let mut lines = body.lines();
-        let (command, version) = SaoriRequest::parse_version_and_command(lines.next())?;
+impl From<SaoriRequestFramingError> for SaoriRequestError {
+    fn from(e: SaoriRequestFramingError) -> SaoriRequestError {
+        SaoriRequestError::Framing(e)
+    }
+}
 
-        let mut security_level: Option<SaoriSecurityLevel> = None;
-        let mut arguments: Vec<String> = Vec::new();
-        let mut sender: Option<String> = None;
+impl From<SaoriRequestLimitError> for SaoriRequestError {
+    fn from(e: SaoriRequestLimitError) -> SaoriRequestError {
+        SaoriRequestError::Limit(e)
+    }
+}
 
-        for line in lines {
-            SaoriRequest::parse_security_level(line, &mut security_level);
-            SaoriRequest::parse_arguments(line, &mut arguments)?;
-            SaoriRequest::parse_sender(line, &mut sender);
+impl SaoriRequestError {
+    /// エラーの番号を返す
+    ///
+    /// 番号はクレートのバージョンをまたいで変わらない。
+    /// Charset関連は11xx、Version関連は12xx、Argument関連は13xx、ヘッダ関連は14xx、
+    /// 改行や終端関連は15xx、上限関連は18xx。
+    pub const fn code(&self) -> u32 {
+        match self {
+            SaoriRequestError::Charset(e) => e.code(),
+            SaoriRequestError::VersionLine(e) => e.code(),
+            SaoriRequestError::Argument(e) => e.code(),
+            SaoriRequestError::Header(e) => e.code(),
+            SaoriRequestError::Framing(e) => e.code(),
+            SaoriRequestError::Limit(e) => e.code(),
         }
-
-        Ok(SaoriRequest {
-            charset,
-            command,
-            version,
-            security_level,
-            arguments,
-            sender,
-        })
     }
 
-    fn read_contents_and_charset(
-        bytes: &[u8],
-    ) -> Result<(String, SaoriCharset), SaoriRequestError> {
-        let temp_string = String::from_utf8_lossy(bytes);
-        let mut temp_lines = temp_string.lines();
-
-        let charset =
-            if let Some(body) = temp_lines.find_map(|v| v.strip_prefix(SAORI_PREFIX_CHARSET)) {
-                SaoriCharset::try_from(body)?
-            } else {
-                SaoriCharset::ShiftJIS
-            };
-
-        match charset.to_encoding().decode(bytes, DecoderTrap::Strict) {
-            Ok(v) => Ok((v, charset)),
-            Err(_) => Err(SaoriRequestError::Charset(
-                SaoriRequestCharsetError::DecodeFailed,
-            )),
+    /// エラーの起きた行(0始まり、リクエスト行が0)を返す
+    ///
+    /// 行によらないエラーの場合は`None`を返す。
+    pub const fn line(&self) -> Option<usize> {
+        match self {
+            SaoriRequestError::VersionLine(SaoriRequestVersionLineError::NoVersion)
+            | SaoriRequestError::VersionLine(SaoriRequestVersionLineError::NoCommand) => Some(0),
+            SaoriRequestError::Argument(SaoriRequestArgumentError::InvalidSeparator(line, _))
+            | SaoriRequestError::Argument(SaoriRequestArgumentError::NoIndex(line, _))
+            | SaoriRequestError::Header(SaoriRequestHeaderError::Unknown(line, _))
+            | SaoriRequestError::Header(SaoriRequestHeaderError::Duplicate(line, _))
+            | SaoriRequestError::Header(SaoriRequestHeaderError::LineBreak(line, _))
+            | SaoriRequestError::Framing(SaoriRequestFramingError::BareLineFeed(line))
+            | SaoriRequestError::Framing(SaoriRequestFramingError::ControlCharacter(line, _))
+            | SaoriRequestError::Limit(SaoriRequestLimitError::LineTooLong(line, _)) => Some(*line),
+            _ => None,
         }
     }
+}
 
-    fn parse_version_and_command(
-        line: Option<&str>,
-    ) -> Result<(SaoriCommand, SaoriVersion), SaoriRequestError> {
-        let line = line.ok_or(SaoriRequestError::VersionLine(
-            SaoriRequestVersionLineError::EmptyRequest,
-        ))?;
-
-        let (command, remain) = if let Some(remain) = line.strip_prefix(SAORI_COMMAND_GET_VERSION) {
-            (SaoriCommand::GetVersion, remain)
-        } else if let Some(remain) = line.strip_prefix(SAORI_COMMAND_EXECUTE) {
-            (SaoriCommand::Execute, remain)
-        } else {
-            return Err(SaoriRequestError::VersionLine(
-                SaoriRequestVersionLineError::NoCommand,
-            ));
-        };
-
-        let version = match remain {
-            r if r == SaoriVersion::V1_0.to_str() => SaoriVersion::V1_0,
-            _ => {
-                return Err(SaoriRequestError::VersionLine(
-                    SaoriRequestVersionLineError::NoVersion,
-                ))
-            }
-        };
-
-        Ok((command, version))
+impl SaoriRequestCharsetError {
+    /// エラーの番号を返す
+    pub const fn code(&self) -> u32 {
+        match self {
+            SaoriRequestCharsetError::DecodeFailed(_, _) => 1101,
+            SaoriRequestCharsetError::UnsupportedCharset => 1102,
+            SaoriRequestCharsetError::EncodeFailed => 1103,
+        }
     }
 
-    fn parse_security_level(line: &str, security_level: &mut Option<SaoriSecurityLevel>) {
-        if let Some(body) = line.strip_prefix(SAORI_PREFIX_SECULITY_LEVEL) {
-            *security_level = match body {
-                b if b == SaoriSecurityLevel::Local.to_str() => Some(SaoriSecurityLevel::Local),
-                b if b == SaoriSecurityLevel::External.to_str() => {
-                    Some(SaoriSecurityLevel::External)
+    /// `bytes`の`offset`の位置で読めなかったことを表すエラーを生成する
+    pub(crate) fn decode_failed(bytes: &[u8], offset: usize) -> SaoriRequestCharsetError {
+        let start = offset.saturating_sub(DECODE_CONTEXT_BYTES);
+        let end = offset
+            .saturating_add(DECODE_CONTEXT_BYTES + 1)
+            .min(bytes.len());
+        let context = bytes
+            .get(start..end)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                if start + i == offset {
+                    format!("[{:02X}]", v)
+                } else {
+                    format!("{:02X}", v)
                 }
-                _ => return,
-            };
-        }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        SaoriRequestCharsetError::DecodeFailed(offset, context)
     }
+}
 
-    fn parse_arguments(line: &str, arguments: &mut Vec<String>) -> Result<(), SaoriRequestError> {
-        if let Some(contents) = line.strip_prefix(SAORI_PREFIX_ARGUMENT) {
-            let (index_raw, value) =
-                contents
-                    .split_once(": ")
-                    .ok_or(SaoriRequestError::Argument(
-                        SaoriRequestArgumentError::InvalidSeparator,
-                    ))?;
-            let index = index_raw
-                .parse::<usize>()
-                .map_err(|_| SaoriRequestError::Argument(SaoriRequestArgumentError::NoIndex))?;
-
-            while arguments.len() <= index {
-                arguments.push(String::new());
-            }
-            arguments[index] = value.to_string()
+impl SaoriRequestVersionLineError {
+    /// エラーの番号を返す
+    pub const fn code(&self) -> u32 {
+        match self {
+            SaoriRequestVersionLineError::EmptyRequest => 1201,
+            SaoriRequestVersionLineError::NoVersion => 1202,
+            SaoriRequestVersionLineError::NoCommand => 1203,
         }
-
-        Ok(())
     }
+}
 
-    fn parse_sender(line: &str, sender: &mut Option<String>) {
-        if let Some(body) = line.strip_prefix(SAORI_PREFIX_SENDER) {
-            *sender = Some(body.to_string())
+impl SaoriRequestArgumentError {
+    /// エラーの番号を返す
+    pub const fn code(&self) -> u32 {
+        match self {
+            SaoriRequestArgumentError::InvalidSeparator(_, _) => 1301,
+            SaoriRequestArgumentError::NoIndex(_, _) => 1302,
         }
     }
+}
 
-    pub fn charset(&self) -> &SaoriCharset {
-        &self.charset
-    }
-    pub fn command(&self) -> &SaoriCommand {
-        &self.command
-    }
-    pub fn version(&self) -> &SaoriVersion {
-        &self.version
-    }
-    pub fn security_level(&self) -> Option<&SaoriSecurityLevel> {
-        self.security_level.as_ref()
-    }
-    pub fn arguments(&self) -> &Vec<String> {
-        &self.arguments
-    }
-    pub fn sender(&self) -> Option<&String> {
-        self.sender.as_ref()
+impl SaoriRequestHeaderError {
+    /// エラーの番号を返す
+    pub const fn code(&self) -> u32 {
+        match self {
+            SaoriRequestHeaderError::Unknown(_, _) => 1401,
+            SaoriRequestHeaderError::Duplicate(_, _) => 1402,
+            SaoriRequestHeaderError::LineBreak(_, _) => 1403,
+        }
     }
 }
 
-impl SaoriCharset {
-    pub fn to_str(&self) -> &'static str {
+impl SaoriRequestBuildError {
+    /// エラーの番号を返す
+    ///
+    /// 番号はクレートのバージョンをまたいで変わらない。SaoriRequestBuilder関連は10xx。
+    pub const fn code(&self) -> u32 {
         match self {
-            SaoriCharset::ShiftJIS => "Shift_JIS",
-            SaoriCharset::EucJP => "EUC-JP",
-            SaoriCharset::UTF8 => "UTF-8",
-            SaoriCharset::ISO2022JP => "ISO-2022-JP",
+            SaoriRequestBuildError::LineBreak(_) => 1001,
+            SaoriRequestBuildError::InvalidHeader(_) => 1002,
         }
     }
+}
 
-    pub fn to_encoding(&self) -> Box<&'static dyn Encoding> {
+impl SaoriRequestFramingError {
+    /// エラーの番号を返す
+    pub const fn code(&self) -> u32 {
         match self {
-            SaoriCharset::ShiftJIS => Box::new(WINDOWS_31J),
-            SaoriCharset::EucJP => Box::new(EUC_JP),
-            SaoriCharset::UTF8 => Box::new(UTF_8),
-            SaoriCharset::ISO2022JP => Box::new(ISO_2022_JP),
+            SaoriRequestFramingError::BareLineFeed(_) => 1501,
+            SaoriRequestFramingError::MissingNul => 1502,
+            SaoriRequestFramingError::ControlCharacter(_, _) => 1503,
         }
     }
 }
 
-impl TryFrom<&str> for SaoriCharset {
-    type Error = SaoriRequestCharsetError;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            v if v == SaoriCharset::ShiftJIS.to_str() => Ok(SaoriCharset::ShiftJIS),
-            v if v == SaoriCharset::EucJP.to_str() => Ok(SaoriCharset::EucJP),
-            v if v == SaoriCharset::UTF8.to_str() => Ok(SaoriCharset::UTF8),
-            v if v == SaoriCharset::ISO2022JP.to_str() => Ok(SaoriCharset::ISO2022JP),
-            _ => Err(SaoriRequestCharsetError::UnsupportedCharset),
+impl SaoriRequestLimitError {
+    /// エラーの番号を返す
+    pub const fn code(&self) -> u32 {
+        match self {
+            SaoriRequestLimitError::TooLarge(_) => 1801,
+            SaoriRequestLimitError::TooManyArguments(_) => 1802,
+            SaoriRequestLimitError::LineTooLong(_, _) => 1803,
         }
     }
 }
 
-impl SaoriCommand {
-    pub fn to_str(&self) -> &'static str {
+impl SaoriArgumentParseError {
+    /// エラーの番号を返す
+    ///
+    /// 番号はクレートのバージョンをまたいで変わらない。Argument*の変換関連は16xx。
+    pub const fn code(&self) -> u32 {
         match self {
-            SaoriCommand::Execute => "EXECUTE",
-            SaoriCommand::GetVersion => "GET Version",
+            SaoriArgumentParseError::Missing(_) => 1601,
+            SaoriArgumentParseError::Invalid(_, _) => 1602,
         }
     }
 }
 
-impl SaoriVersion {
-    pub fn to_str(&self) -> &'static str {
+impl SaoriParseWarning {
+    /// 警告の番号を返す
+    ///
+    /// 番号はクレートのバージョンをまたいで変わらない。読み取り時の警告は17xx。
+    pub const fn code(&self) -> u32 {
         match self {
-            SaoriVersion::V1_0 => "SAORI/1.0",
+            SaoriParseWarning::UnknownSecurityLevel(_, _) => 1701,
+            SaoriParseWarning::MalformedLine(_, _) => 1702,
+            SaoriParseWarning::HeaderCase(_, _) => 1703,
+            SaoriParseWarning::CharsetCorrected(_, _, _) => 1704,
+            SaoriParseWarning::OrphanContinuation(_, _) => 1705,
         }
     }
-}
 
-impl SaoriSecurityLevel {
-    pub fn to_str(&self) -> &'static str {
+    /// 問題のあった行(0始まり)
+    pub const fn line(&self) -> usize {
         match self {
-            SaoriSecurityLevel::Local => "Local",
-            SaoriSecurityLevel::External => "External",
+            SaoriParseWarning::UnknownSecurityLevel(line, _)
+            | SaoriParseWarning::MalformedLine(line, _)
+            | SaoriParseWarning::HeaderCase(line, _)
+            | SaoriParseWarning::CharsetCorrected(line, _, _)
+            | SaoriParseWarning::OrphanContinuation(line, _) => *line,
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    mod saori_request {
-        use super::*;
+impl SaoriDuplicateHeaderPolicy {
+    /// 既に値があるときに、後の値で置き換えるかどうか
+    fn replaces(&self) -> bool {
+        *self != SaoriDuplicateHeaderPolicy::FirstWins
+    }
+}
 
-        mod new {
-            use encoding::EncoderTrap;
+impl Default for SaoriFraming {
+    fn default() -> Self {
+        SaoriFraming {
+            line_ending: SaoriLineEnding::CrLf,
+            has_nul: true,
+            has_bom: false,
+        }
+    }
+}
 
-            use super::*;
+impl SaoriFraming {
+    pub fn new(line_ending: SaoriLineEnding, has_nul: bool, has_bom: bool) -> SaoriFraming {
+        SaoriFraming {
+            line_ending,
+            has_nul,
+            has_bom,
+        }
+    }
 
-            #[test]
-            fn success_when_valid_bytes() {
-                let case_raw = "GET Version SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n";
-                let case = WINDOWS_31J.encode(&case_raw, EncoderTrap::Strict).unwrap();
-                let result = SaoriRequest::new(&case).unwrap();
-                assert_eq!(result.charset(), &SaoriCharset::ShiftJIS);
-                assert_eq!(result.command(), &SaoriCommand::GetVersion);
-                assert_eq!(result.version(), &SaoriVersion::V1_0);
-                assert_eq!(result.security_level(), None);
-                assert_eq!(result.arguments(), &Vec::<String>::new());
-                assert_eq!(result.sender(), None);
-            }
+    /// 内容から改行の種類と、末尾の`\0`の有無を読み取る
+    fn detect(body: &str) -> SaoriFraming {
+        let line_feeds = body.matches('\n').count();
+        let crlfs = body.matches("\r\n").count();
+        let line_ending = match (crlfs, line_feeds) {
+            (c, l) if c == l => SaoriLineEnding::CrLf,
+            (0, _) => SaoriLineEnding::Lf,
+            _ => SaoriLineEnding::Mixed,
+        };
 
-            #[test]
-            fn failed_when_invalid_bytes() {
-                let case_raw = "GET SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n";
-                let case = WINDOWS_31J.encode(&case_raw, EncoderTrap::Strict).unwrap();
-                assert!(SaoriRequest::new(&case).is_err());
-            }
+        SaoriFraming {
+            line_ending,
+            has_nul: body.ends_with('\0'),
+            has_bom: false,
         }
+    }
 
-        mod read_contents_and_charset {
-            use encoding::EncoderTrap;
+    pub fn line_ending(&self) -> SaoriLineEnding {
+        self.line_ending
+    }
 
-            use super::*;
+    /// 末尾が`\0`だったかどうか
+    pub fn has_nul(&self) -> bool {
+        self.has_nul
+    }
 
-            #[test]
-            fn success_when_valid_bytes() {
-                let case_raw = "GET Version SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n";
-                let case = WINDOWS_31J.encode(&case_raw, EncoderTrap::Strict).unwrap();
-                let (contents, charset) = SaoriRequest::read_contents_and_charset(&case).unwrap();
-                assert_eq!(contents.as_str(), case_raw);
-                assert_eq!(charset, SaoriCharset::ShiftJIS);
-            }
+    /// 先頭にBOMがあったかどうか
+    pub fn has_bom(&self) -> bool {
+        self.has_bom
+    }
+}
 
-            #[test]
-            fn failed_when_invalid_bytes() {
-                let case_raw =
-                    "EXECUTE SHIORI/1.0\r\nCharset: UTF-8\r\nArgument0: あいうえお\r\n\r\n";
-                let case = WINDOWS_31J.encode(&case_raw, EncoderTrap::Strict).unwrap();
-                assert!(SaoriRequest::read_contents_and_charset(&case).is_err());
-            }
-        }
+impl SaoriLimits {
+    pub fn new() -> SaoriLimits {
+        SaoriLimits::default()
+    }
 
-        mod parse_versioni_and_command {
-            use super::*;
+    /// リクエスト全体の最大バイト数(デコード前)
+    pub fn max_bytes(mut self, max_bytes: usize) -> SaoriLimits {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
 
-            #[test]
-            fn success_when_valid_str_get_version() {
-                let case = Some("GET Version SAORI/1.0");
-                let (command, version) = SaoriRequest::parse_version_and_command(case).unwrap();
-                assert_eq!(command, SaoriCommand::GetVersion);
-                assert_eq!(version, SaoriVersion::V1_0);
-            }
+    /// Argument*の最大数(Argument0から数える)
+    ///
+    /// 設定しない場合も、番号がデコードした内容のバイト数以上のArgument*はエラーになる。
+    pub fn max_arguments(mut self, max_arguments: usize) -> SaoriLimits {
+        self.max_arguments = Some(max_arguments);
+        self
+    }
 
-            #[test]
-            fn success_when_valid_str_execute() {
-                let case = Some("EXECUTE SAORI/1.0");
-                let (command, version) = SaoriRequest::parse_version_and_command(case).unwrap();
-                assert_eq!(command, SaoriCommand::Execute);
-                assert_eq!(version, SaoriVersion::V1_0);
-            }
+    /// 1行の最大バイト数(デコード後、改行を除く)
+    pub fn max_line_length(mut self, max_line_length: usize) -> SaoriLimits {
+        self.max_line_length = Some(max_line_length);
+        self
+    }
+}
 
-            #[test]
-            fn failed_when_invalid_command() {
-                let case = Some("SOMETHINGWRONG SAORI/1.0");
-                assert!(SaoriRequest::parse_version_and_command(case).is_err());
-            }
+impl SaoriParseOptions {
+    pub fn new() -> SaoriParseOptions {
+        SaoriParseOptions::default()
+    }
 
-            #[test]
-            fn failed_when_invalid_version() {
-                let case = Some("EXECUTE SAORI1.0");
-                assert!(SaoriRequest::parse_version_and_command(case).is_err());
-            }
+    /// 仕様にないヘッダや、重複したヘッダをエラーにするかどうか
+    pub fn strict(mut self, strict: bool) -> SaoriParseOptions {
+        self.strict = strict;
+        self
+    }
 
-            #[test]
+    /// 同じヘッダが複数あったときの扱い
+    ///
+    /// strictのときは、この設定にかかわらずエラーになる。
+    pub fn duplicate_headers(mut self, policy: SaoriDuplicateHeaderPolicy) -> SaoriParseOptions {
+        self.duplicate_headers = policy;
+        self
+    }
+
+    /// 仕様にないコマンドを`SaoriCommand::Other`として受け入れるかどうか
+    pub fn accept_unknown_command(mut self, accept_unknown_command: bool) -> SaoriParseOptions {
+        self.accept_unknown_command = accept_unknown_command;
+        self
+    }
+
+    /// `SAORI/`で始まる、対応していないバージョンを`SaoriVersion::Other`として受け入れるかどうか
+    pub fn accept_unknown_version(mut self, accept_unknown_version: bool) -> SaoriParseOptions {
+        self.accept_unknown_version = accept_unknown_version;
+        self
+    }
+
+    /// 各行の前後の空白を取り除いてから読み取るかどうか
+    pub fn trim_whitespace(mut self, trim_whitespace: bool) -> SaoriParseOptions {
+        self.trim_whitespace = trim_whitespace;
+        self
+    }
+
+    /// 改行がすべてCRLFであることを求めるかどうか
+    pub fn require_crlf(mut self, require_crlf: bool) -> SaoriParseOptions {
+        self.require_crlf = require_crlf;
+        self
+    }
+
+    /// 末尾が`\0`であることを求めるかどうか
+    pub fn require_nul(mut self, require_nul: bool) -> SaoriParseOptions {
+        self.require_nul = require_nul;
+        self
+    }
+
+    /// Charsetヘッダがないときに使うCharset(既定ではShift_JIS)
+    pub fn default_charset(mut self, default_charset: SaoriCharset) -> SaoriParseOptions {
+        self.default_charset = default_charset;
+        self
+    }
+
+    /// 途中の`\0`や制御文字の扱い(既定ではそのまま受け入れる)
+    pub fn control_characters(
+        mut self,
+        control_characters: SaoriControlCharacterPolicy,
+    ) -> SaoriParseOptions {
+        self.control_characters = control_characters;
+        self
+    }
+
+    /// 空白かタブで始まる行を、前のヘッダの値の続きとして読み取るかどうか
+    ///
+    /// 続きの行は、先頭の1文字を取り除き、空白1つでつないで前の値に加える
+    /// (値に改行は残らない)。このとき、エラーや警告の行番号はつないだ後の行で数える。
+    /// 前にヘッダがない続きの行は無視し、[`SaoriParseWarning::OrphanContinuation`] を記録する。
+    pub fn unfold_lines(mut self, unfold_lines: bool) -> SaoriParseOptions {
+        self.unfold_lines = unfold_lines;
+        self
+    }
+
+    /// 宣言されたCharsetで明らかに読めないときに、内容からCharsetを推定するかどうか
+    ///
+    /// BOMのない内容が、非ASCIIを含む正しいUTF-8であればUTF-8とみなす。
+    /// そうでなければ、Shift_JISにしかない先行バイトの有無でShift_JISとEUC-JPを順に試す。
+    /// 置き換えたときは [`SaoriParseWarning::CharsetCorrected`] を記録する。
+    pub fn detect_charset(mut self, detect_charset: bool) -> SaoriParseOptions {
+        self.detect_charset = detect_charset;
+        self
+    }
+
+    /// 読み取るときの上限
+    pub fn limits(mut self, limits: SaoriLimits) -> SaoriParseOptions {
+        self.limits = limits;
+        self
+    }
+
+    /// 受け取ったバイト列をそのまま保持するかどうか
+    pub fn keep_raw_bytes(mut self, keep_raw_bytes: bool) -> SaoriParseOptions {
+        self.keep_raw_bytes = keep_raw_bytes;
+        self
+    }
+}
+
+impl SaoriRequest {
+    pub fn new(bytes: &[u8]) -> Result<SaoriRequest, SaoriRequestError> {
+        SaoriRequest::new_with_options(bytes, &SaoriParseOptions::default())
+    }
+
+    /// 設定に従って、バイト列から自身を生成する
+    pub fn new_with_options(
+        bytes: &[u8],
+        options: &SaoriParseOptions,
+    ) -> Result<SaoriRequest, SaoriRequestError> {
+        SaoriRequest::new_with_warnings(bytes, options).map(|(request, _)| request)
+    }
+
+    /// 設定に従って、バイト列から自身を生成し、読み取り中の警告とともに返す
+    ///
+    /// ```
+    /// use saori_interface_rs::*;
+    ///
+    /// let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSecurityLevel: Remote\r\n\r\n\0";
+    /// let (request, warnings) =
+    ///     SaoriRequest::new_with_warnings(request_raw.as_bytes(), &SaoriParseOptions::new()).unwrap();
+    ///
+    /// // testing
+    /// assert_eq!(
+    ///     request.security_level(),
+    ///     Some(&SaoriSecurityLevel::Other("Remote".to_string()))
+    /// );
+    /// assert_eq!(
+    ///     warnings,
+    ///     vec![SaoriParseWarning::UnknownSecurityLevel(2, "Remote".to_string())]
+    /// );
+    /// ```
+    pub fn new_with_warnings(
+        bytes: &[u8],
+        options: &SaoriParseOptions,
+    ) -> Result<(SaoriRequest, Vec<SaoriParseWarning>), SaoriRequestError> {
+        if let Some(max_bytes) = options.limits.max_bytes {
+            if bytes.len() > max_bytes {
+                return Err(SaoriRequestLimitError::TooLarge(bytes.len()).into());
+            }
+        }
+        let (body, charset, correction) = SaoriRequest::read_contents(bytes, options)?;
+        let (mut request, mut warnings) = SaoriRequest::parse_contents(body, charset, options)?;
+        if let Some(correction) = correction {
+            warnings.insert(0, correction);
+        }
+        request.warnings = warnings.clone();
+        if options.keep_raw_bytes {
+            request.raw_bytes = Some(bytes.to_vec());
+        }
+        request.framing.has_bom = request
+            .charset
+            .bom()
+            .is_some_and(|bom| bytes.starts_with(bom));
+
+        Ok((request, warnings))
+    }
+
+    /// 設定に従って、バイト列から自身を生成し、読み取り中の警告を [`SaoriDiagnostics`] として返す
+    ///
+    /// ```
+    /// use saori_interface_rs::*;
+    ///
+    /// let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nsender: materia\r\n\r\n\0";
+    /// let (_, diagnostics) =
+    ///     SaoriRequest::new_with_diagnostics(request_raw.as_bytes(), &SaoriParseOptions::new())
+    ///         .unwrap();
+    ///
+    /// // testing
+    /// assert!(!diagnostics.has_errors());
+    /// assert_eq!(diagnostics.max_severity(), Some(SaoriSeverity::Warning));
+    /// ```
+    pub fn new_with_diagnostics(
+        bytes: &[u8],
+        options: &SaoriParseOptions,
+    ) -> Result<(SaoriRequest, SaoriDiagnostics), SaoriRequestError> {
+        let (request, warnings) = SaoriRequest::new_with_warnings(bytes, options)?;
+        let diagnostics = warnings.iter().map(SaoriDiagnostic::from).collect();
+        Ok((request, diagnostics))
+    }
+
+    /// ベースウェアから渡されたポインタと長さから自身を生成する
+    ///
+    /// 内容は読み取り時に複製されるため、戻った後は`ptr`の領域を解放してよい
+    /// (解放するのは呼び出し側の責任で、このクレートは所有しない)。
+    /// `ptr`がnullの場合は`SaoriRequestVersionLineError::EmptyRequest`を返す。
+    ///
+    /// # Safety
+    ///
+    /// `ptr`がnullでない場合、`len`バイトの読み取りに対して有効で、
+    /// 呼び出している間に書き換えられないこと。
+    ///
+    /// ```
+    /// use saori_interface_rs::*;
+    ///
+    /// let request_raw = b"EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+    /// let request = unsafe {
+    ///     SaoriRequest::from_raw_parts(request_raw.as_ptr() as *const i8, request_raw.len())
+    /// }
+    /// .unwrap();
+    ///
+    /// // testing
+    /// assert_eq!(request.command(), &SaoriCommand::Execute);
+    /// ```
+    pub unsafe fn from_raw_parts(
+        ptr: *const i8,
+        len: usize,
+    ) -> Result<SaoriRequest, SaoriRequestError> {
+        if ptr.is_null() {
+            return Err(SaoriRequestVersionLineError::EmptyRequest.into());
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, len) };
+        SaoriRequest::new(bytes)
+    }
+
+    /// ベースウェアから渡された、`\0`で終わる内容へのポインタから自身を生成する
+    ///
+    /// 最大で`max_len`バイトまで`\0`を探し、見つからない場合は
+    /// `SaoriRequestFramingError::MissingNul`を返す。所有権の扱いは`from_raw_parts`と同じ。
+    ///
+    /// # Safety
+    ///
+    /// `ptr`がnullでない場合、`\0`が見つかるまで、または`max_len`バイトまでの読み取りに対して有効で、
+    /// 呼び出している間に書き換えられないこと。
+    pub unsafe fn from_nul_terminated_ptr(
+        ptr: *const i8,
+        max_len: usize,
+    ) -> Result<SaoriRequest, SaoriRequestError> {
+        if ptr.is_null() {
+            return Err(SaoriRequestVersionLineError::EmptyRequest.into());
+        }
+        let ptr = ptr as *const u8;
+        let len = (0..max_len)
+            .find(|i| unsafe { *ptr.add(*i) } == 0)
+            .ok_or(SaoriRequestFramingError::MissingNul)?;
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len + 1) };
+        SaoriRequest::new(bytes)
+    }
+
+    /// UTF-16の内容から自身を生成する
+    ///
+    /// 先頭のBOMは取り除く。Charsetはヘッダから記録するだけで、内容はUTF-16として読み取る。
+    pub fn from_utf16(units: &[u16]) -> Result<SaoriRequest, SaoriRequestError> {
+        let body = String::from_utf16(units).map_err(|_| {
+            // 位置と前後のバイトは、UTF-16LEのバイト列として数える
+            let valid = char::decode_utf16(units.iter().copied())
+                .map_while(Result::ok)
+                .map(char::len_utf16)
+                .sum::<usize>();
+            let bytes: Vec<u8> = units.iter().flat_map(|v| v.to_le_bytes()).collect();
+            SaoriRequestCharsetError::decode_failed(&bytes, valid * 2)
+        })?;
+        body.strip_prefix('\u{FEFF}').unwrap_or(&body).parse()
+    }
+
+    /// ホストから渡された、UTF-16の内容へのポインタと長さ(`u16`の数)から自身を生成する
+    ///
+    /// 所有権の扱いは`from_raw_parts`と同じ。
+    ///
+    /// # Safety
+    ///
+    /// `ptr`がnullでない場合、`len`個の`u16`の読み取りに対して有効で、
+    /// 呼び出している間に書き換えられないこと。
+    pub unsafe fn from_wide_ptr(
+        ptr: *const u16,
+        len: usize,
+    ) -> Result<SaoriRequest, SaoriRequestError> {
+        if ptr.is_null() {
+            return Err(SaoriRequestVersionLineError::EmptyRequest.into());
+        }
+        let units = unsafe { std::slice::from_raw_parts(ptr, len) };
+        SaoriRequest::from_utf16(units)
+    }
+
+    /// リクエストのバイト列を、Charsetを`target`に変えたバイト列に書き換える
+    ///
+    /// Charsetヘッダを書き換え(ない場合はリクエスト行の次に加え)、内容を`target`でエンコードし直す。
+    /// 改行や仕様にないヘッダはそのまま残す。
+    /// `target`で表せない文字がある場合は`SaoriRequestCharsetError::EncodeFailed`を返す。
+    ///
+    /// ```
+    /// use saori_interface_rs::*;
+    ///
+    /// let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: 表示\r\n\r\n\0";
+    /// let result = SaoriRequest::transcode(request_raw.as_bytes(), SaoriCharset::ShiftJIS).unwrap();
+    /// let request = SaoriRequest::new(&result).unwrap();
+    ///
+    /// // testing
+    /// assert_eq!(request.charset(), &SaoriCharset::ShiftJIS);
+    /// assert_eq!(request.argument(0), Some("表示"));
+    /// ```
+    pub fn transcode(bytes: &[u8], target: SaoriCharset) -> Result<Vec<u8>, SaoriRequestError> {
+        let (body, _) =
+            SaoriRequest::read_contents_and_charset(bytes, &SaoriParseOptions::default())?;
+
+        let has_charset = body
+            .lines()
+            .skip(1)
+            .any(|v| v.starts_with(SAORI_PREFIX_CHARSET));
+        let mut result = String::with_capacity(body.len() + SAORI_PREFIX_CHARSET.len() + 16);
+        for (index, line) in body.split_inclusive('\n').enumerate() {
+            let content = line.trim_end_matches(['\r', '\n']);
+            let end = match &line[content.len()..] {
+                "" => "\r\n",
+                end => end,
+            };
+            if index > 0 && content.starts_with(SAORI_PREFIX_CHARSET) {
+                result.push_str(SAORI_PREFIX_CHARSET);
+                result.push_str(target.to_str());
+                result.push_str(&line[content.len()..]);
+                continue;
+            }
+
+            result.push_str(line);
+            if index == 0 && !has_charset {
+                if line == content {
+                    result.push_str(end);
+                }
+                result.push_str(SAORI_PREFIX_CHARSET);
+                result.push_str(target.to_str());
+                result.push_str(end);
+            }
+        }
+
+        let mut encoded = Vec::new();
+        target
+            .encode_to(&result, &mut encoded, |_| None)
+            .map_err(|_| SaoriRequestCharsetError::EncodeFailed)?;
+        Ok(encoded)
+    }
+
+    /// デコード済みの内容から自身を生成する
+    fn parse_contents(
+        body: String,
+        charset: SaoriCharset,
+        options: &SaoriParseOptions,
+    ) -> Result<(SaoriRequest, Vec<SaoriParseWarning>), SaoriRequestError> {
+        SaoriRequest::check_framing(&body, options)?;
+        let framing = SaoriFraming::detect(&body);
+        let body = SaoriRequest::check_control_characters(body, options.control_characters)?;
+        SaoriRequest::check_line_length(&body, &options.limits)?;
+
+        let trim_whitespace = options.trim_whitespace;
+        let lines = SaoriRequest::unfold_lines(&body, options.unfold_lines);
+        let mut lines =
+            lines
+                .iter()
+                .map(|v| v.as_ref())
+                .map(move |v| if trim_whitespace { v.trim() } else { v });
+        let (command, version) = SaoriRequest::parse_version_and_command(lines.next(), options)?;
+
+        if options.strict || options.duplicate_headers == SaoriDuplicateHeaderPolicy::Error {
+            SaoriRequest::check_headers(lines.clone().enumerate(), options.strict)?;
+        }
+
+        // 上限がなくても、埋める数は内容のバイト数までにする
+        let max_arguments = options
+            .limits
+            .max_arguments
+            .map_or(body.len(), |max| max.min(body.len()));
+        let mut security_level: Option<SaoriSecurityLevel> = None;
+        let mut arguments: Vec<Option<String>> = Vec::new();
+        let mut sender: Option<String> = None;
+        let mut extra_headers: Vec<(String, String)> = Vec::new();
+        let mut warnings: Vec<SaoriParseWarning> = Vec::new();
+
+        for (index, line) in lines.enumerate() {
+            // つなげられずに残った続きの行は、ヘッダとして読まない
+            if options.unfold_lines && line.starts_with([' ', '\t']) {
+                warnings.push(SaoriParseWarning::OrphanContinuation(
+                    index + 1,
+                    line.to_string(),
+                ));
+                continue;
+            }
+            SaoriRequest::check_warnings(index + 1, line, &mut warnings);
+            SaoriRequest::parse_security_level(
+                line,
+                &mut security_level,
+                options.duplicate_headers,
+            );
+            SaoriRequest::parse_arguments(index + 1, line, &mut arguments, max_arguments, options)?;
+            SaoriRequest::parse_sender(line, &mut sender, options.duplicate_headers);
+            SaoriRequest::parse_extra_header(line, &mut extra_headers);
+        }
+
+        let arguments_present = arguments.iter().map(Option::is_some).collect();
+        let arguments = arguments
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect();
+
+        let request = SaoriRequest {
+            charset,
+            command,
+            version,
+            security_level,
+            arguments,
+            arguments_present,
+            sender,
+            extra_headers,
+            raw_bytes: None,
+            framing,
+            warnings: Vec::new(),
+        };
+
+        Ok((request, warnings))
+    }
+
+    pub(crate) fn read_contents_and_charset(
+        bytes: &[u8],
+        options: &SaoriParseOptions,
+    ) -> Result<(String, SaoriCharset), SaoriRequestCharsetError> {
+        SaoriRequest::read_contents(bytes, options).map(|(body, charset, _)| (body, charset))
+    }
+
+    /// デコードした内容とCharsetを、Charsetを推定し直した場合はその警告とともに返す
+    fn read_contents(
+        raw: &[u8],
+        options: &SaoriParseOptions,
+    ) -> Result<(String, SaoriCharset, Option<SaoriParseWarning>), SaoriRequestCharsetError> {
+        let mut correction = None;
+        let (bytes, charset) = match SaoriRequest::strip_bom(raw) {
+            (bytes, Some(charset)) => (bytes, charset),
+            (bytes, None) => match SaoriRequest::detect_utf16(bytes) {
+                Some(charset) => (bytes, charset),
+                None => {
+                    let declared = SaoriRequest::read_charset(bytes, options)?;
+                    match SaoriRequest::correct_charset(bytes, &declared, options) {
+                        Some(detected) => {
+                            let line = SaoriRequest::charset_line(bytes, options);
+                            correction = Some(SaoriParseWarning::CharsetCorrected(
+                                line,
+                                declared,
+                                detected.clone(),
+                            ));
+                            (bytes, detected)
+                        }
+                        None => (bytes, declared),
+                    }
+                }
+            },
+        };
+
+        match charset.decode(bytes) {
+            Ok(v) => Ok((v.into_owned(), charset, correction)),
+            Err(offset) => {
+                let bom_len = raw.len() - bytes.len();
+                Err(SaoriRequestCharsetError::decode_failed(
+                    raw,
+                    bom_len + offset,
+                ))
+            }
+        }
+    }
+
+    /// 宣言されたCharsetが明らかに誤っている場合に、推定したCharsetを返す
+    ///
+    /// 宣言されたCharsetで読めないか、UTF-8以外の宣言で内容が非ASCIIを含む正しいUTF-8の場合を、
+    /// 明らかな誤りとみなす。
+    fn correct_charset(
+        bytes: &[u8],
+        declared: &SaoriCharset,
+        options: &SaoriParseOptions,
+    ) -> Option<SaoriCharset> {
+        if !options.detect_charset {
+            return None;
+        }
+
+        let detected = SaoriRequest::detect_charset(bytes)?;
+        let wrong = declared.decode(bytes).is_err() || detected == SaoriCharset::UTF8;
+        (wrong && detected != *declared).then_some(detected)
+    }
+
+    /// BOMのない内容から、Charsetを推定する
+    ///
+    /// ASCIIのみの場合は、どのCharsetでも同じに読めるため`None`を返す。
+    fn detect_charset(bytes: &[u8]) -> Option<SaoriCharset> {
+        if bytes.is_ascii() {
+            return None;
+        }
+        if std::str::from_utf8(bytes).is_ok() {
+            return Some(SaoriCharset::UTF8);
+        }
+
+        // 0x81-0x9FはShift_JISの先行バイトで、EUC-JPでは0x8E、0x8F(シングルシフト)以外に現れない
+        let sjis_leads = bytes
+            .iter()
+            .filter(|v| (0x81..=0x9F).contains(*v) && !matches!(v, 0x8E | 0x8F))
+            .count();
+        let candidates = if sjis_leads > 0 {
+            [SaoriCharset::ShiftJIS, SaoriCharset::EucJP]
+        } else {
+            [SaoriCharset::EucJP, SaoriCharset::ShiftJIS]
+        };
+        candidates
+            .into_iter()
+            .find(|charset| charset.decode(bytes).is_ok())
+    }
+
+    /// Charsetヘッダのある行(0始まり)。ない場合はリクエスト行の0を返す。
+    fn charset_line(bytes: &[u8], options: &SaoriParseOptions) -> usize {
+        SaoriRequest::find_charset_label(bytes, options).map_or(0, |label| {
+            let offset = label.as_ptr() as usize - bytes.as_ptr() as usize;
+            bytes[..offset].iter().filter(|v| **v == b'\n').count()
+        })
+    }
+
+    /// Charsetヘッダの値を読み取る。ない場合は設定の既定のCharsetを返す。
+    fn read_charset(
+        bytes: &[u8],
+        options: &SaoriParseOptions,
+    ) -> Result<SaoriCharset, SaoriRequestCharsetError> {
+        match SaoriRequest::find_charset_label(bytes, options) {
+            Some(label) => Ok(std::str::from_utf8(label)
+                .map_err(|_| SaoriRequestCharsetError::UnsupportedCharset)?
+                .parse()?),
+            None => Ok(options.default_charset.clone()),
+        }
+    }
+
+    /// 先頭のBOMを取り除き、BOMが示すCharsetを返す
+    ///
+    /// BOMはCharsetヘッダより優先する。
+    fn strip_bom(bytes: &[u8]) -> (&[u8], Option<SaoriCharset>) {
+        [
+            SaoriCharset::UTF8,
+            SaoriCharset::UTF16LE,
+            SaoriCharset::UTF16BE,
+        ]
+        .into_iter()
+        .find_map(|charset| {
+            let remain = bytes.strip_prefix(charset.bom()?)?;
+            Some((remain, Some(charset)))
+        })
+        .unwrap_or((bytes, None))
+    }
+
+    /// BOMのないUTF-16を、先頭の文字(ASCIIのコマンド)のバイト並びから判別する
+    ///
+    /// UTF-16ではCharsetヘッダをバイト単位で探せないため、ヘッダより先に判別する。
+    fn detect_utf16(bytes: &[u8]) -> Option<SaoriCharset> {
+        match bytes {
+            [first, 0, ..] if *first != 0 => Some(SaoriCharset::UTF16LE),
+            [0, second, ..] if *second != 0 => Some(SaoriCharset::UTF16BE),
+            _ => None,
+        }
+    }
+
+    /// デコードする前のバイト列から、Charsetヘッダの値を探す
+    ///
+    /// 複数ある場合は、重複したヘッダの扱いに従って最初か最後のものを返す。
+    /// 対応しているCharset(ISO-2022-JP以外はASCII互換)では、`\n`のバイトが
+    /// 2バイト文字の一部に現れないため、バイト単位で探してよい。
+    fn find_charset_label<'a>(bytes: &'a [u8], options: &SaoriParseOptions) -> Option<&'a [u8]> {
+        let mut labels = bytes
+            .split(|v| *v == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .map(|line| {
+                if options.trim_whitespace {
+                    line.trim_ascii()
+                } else {
+                    line
+                }
+            })
+            .filter_map(|line| line.strip_prefix(SAORI_PREFIX_CHARSET.as_bytes()));
+        if options.duplicate_headers.replaces() {
+            labels.next_back()
+        } else {
+            labels.next()
+        }
+    }
+
+    /// 設定に従って、改行と終端を確かめる
+    fn check_framing(body: &str, options: &SaoriParseOptions) -> Result<(), SaoriRequestError> {
+        if options.require_crlf {
+            let bytes = body.as_bytes();
+            let bare = bytes
+                .iter()
+                .enumerate()
+                .position(|(i, v)| *v == b'\n' && (i == 0 || bytes[i - 1] != b'\r'));
+            if let Some(position) = bare {
+                let line = bytes[..position].iter().filter(|v| **v == b'\n').count();
+                return Err(SaoriRequestFramingError::BareLineFeed(line).into());
+            }
+        }
+        if options.require_nul && !body.ends_with('\0') {
+            return Err(SaoriRequestFramingError::MissingNul.into());
+        }
+
+        Ok(())
+    }
+
+    /// 設定に従って、途中の`\0`やタブ以外のC0制御文字を拒否するか取り除く
+    ///
+    /// 末尾の終端の`\0`は対象にしない。
+    fn check_control_characters(
+        body: String,
+        policy: SaoriControlCharacterPolicy,
+    ) -> Result<String, SaoriRequestError> {
+        let is_target = |c: char| c.is_ascii_control() && !matches!(c, '\t' | '\n' | '\x7F');
+        let (contents, terminator) = match body.strip_suffix('\0') {
+            Some(contents) => (contents, "\0"),
+            None => (body.as_str(), ""),
+        };
+
+        match policy {
+            SaoriControlCharacterPolicy::Allow => Ok(body),
+            SaoriControlCharacterPolicy::Reject => {
+                for (index, line) in contents.split('\n').enumerate() {
+                    let line = line.strip_suffix('\r').unwrap_or(line);
+                    if let Some(c) = line.chars().find(|c| is_target(*c)) {
+                        return Err(SaoriRequestFramingError::ControlCharacter(index, c).into());
+                    }
+                }
+                Ok(body)
+            }
+            SaoriControlCharacterPolicy::Strip => {
+                let mut result: String = contents
+                    .split_inclusive('\n')
+                    .flat_map(|line| {
+                        let (line, end) = match line.strip_suffix("\r\n") {
+                            Some(line) => (line, "\r\n"),
+                            None => match line.strip_suffix('\n') {
+                                Some(line) => (line, "\n"),
+                                None => (line, ""),
+                            },
+                        };
+                        line.chars().filter(|c| !is_target(*c)).chain(end.chars())
+                    })
+                    .collect();
+                result.push_str(terminator);
+                Ok(result)
+            }
+        }
+    }
+
+    /// `unfold`のときは、空白かタブで始まる行を前のヘッダの行につなぐ
+    fn unfold_lines(body: &str, unfold: bool) -> Vec<Cow<'_, str>> {
+        let mut lines: Vec<Cow<'_, str>> = Vec::new();
+
+        for line in body.lines() {
+            let continuation = line.strip_prefix([' ', '\t']).filter(|_| {
+                unfold && lines.len() > 1 && lines.last().is_some_and(|v| !v.is_empty())
+            });
+            match (continuation, lines.last_mut()) {
+                (Some(continuation), Some(last)) => {
+                    let last = last.to_mut();
+                    last.push(' ');
+                    last.push_str(continuation);
+                }
+                _ => lines.push(Cow::Borrowed(line)),
+            }
+        }
+
+        lines
+    }
+
+    /// 上限を超える長さの行がないか確かめる
+    fn check_line_length(body: &str, limits: &SaoriLimits) -> Result<(), SaoriRequestError> {
+        if let Some(max_line_length) = limits.max_line_length {
+            if let Some((index, line)) = body
+                .lines()
+                .enumerate()
+                .find(|(_, line)| line.len() > max_line_length)
+            {
+                return Err(SaoriRequestLimitError::LineTooLong(index, line.len()).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_version_and_command(
+        line: Option<&str>,
+        options: &SaoriParseOptions,
+    ) -> Result<(SaoriCommand, SaoriVersion), SaoriRequestError> {
+        let line = line.ok_or(SaoriRequestError::VersionLine(
+            SaoriRequestVersionLineError::EmptyRequest,
+        ))?;
+
+        let (command, remain) = if let Some(remain) = line.strip_prefix(SAORI_COMMAND_GET_VERSION) {
+            (SaoriCommand::GetVersion, remain)
+        } else if let Some(remain) = line.strip_prefix(SAORI_COMMAND_EXECUTE) {
+            (SaoriCommand::Execute, remain)
+        } else if let Some((command, remain)) = line
+            .rsplit_once(' ')
+            .filter(|(command, _)| options.accept_unknown_command && !command.is_empty())
+        {
+            (SaoriCommand::Other(command.to_string()), remain)
+        } else {
+            return Err(SaoriRequestError::VersionLine(
+                SaoriRequestVersionLineError::NoCommand,
+            ));
+        };
+
+        let version = match remain {
+            r if r == SaoriVersion::V1_0.to_str() => SaoriVersion::V1_0,
+            r if options.accept_unknown_version && r.starts_with(SAORI_VERSION_PREFIX) => {
+                SaoriVersion::Other(r.to_string())
+            }
+            _ => {
+                return Err(SaoriRequestError::VersionLine(
+                    SaoriRequestVersionLineError::NoVersion,
+                ))
+            }
+        };
+
+        Ok((command, version))
+    }
+
+    /// 仕様にあるヘッダが重複していないか、`reject_unknown`のときは仕様にあるものかも確かめる
+    ///
+    /// `lines`は、リクエスト行の次からの行と、その0始まりの番号の組。
+    /// 空行か終端の`\0`の行で止める。
+    fn check_headers<'a>(
+        lines: impl Iterator<Item = (usize, &'a str)>,
+        reject_unknown: bool,
+    ) -> Result<(), SaoriRequestError> {
+        let mut seen: HashSet<String> = HashSet::new();
+
+        for (index, line) in lines.take_while(|(_, v)| !v.is_empty() && *v != "\0") {
+            let index = index + 1;
+            let name = line.split_once(':').map_or(line, |(name, _)| name);
+            let key = if [
+                SAORI_PREFIX_CHARSET,
+                SAORI_PREFIX_SECULITY_LEVEL,
+                SAORI_PREFIX_SENDER,
+            ]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+            {
+                name.to_string()
+            } else if let Some(index) = name
+                .strip_prefix(SAORI_PREFIX_ARGUMENT)
+                .and_then(|v| v.parse::<usize>().ok())
+            {
+                format!("{}{}", SAORI_PREFIX_ARGUMENT, index)
+            } else if reject_unknown {
+                return Err(SaoriRequestHeaderError::Unknown(index, name.to_string()).into());
+            } else {
+                // 仕様にないヘッダは、重複していてもそのまま残す
+                continue;
+            };
+
+            if seen.contains(&key) {
+                return Err(SaoriRequestHeaderError::Duplicate(index, key).into());
+            }
+            seen.insert(key);
+        }
+
+        Ok(())
+    }
+
+    /// 読み取りは続けられるが、仕様から外れている行を`warnings`に加える
+    fn check_warnings(index: usize, line: &str, warnings: &mut Vec<SaoriParseWarning>) {
+        if line.is_empty() || line == "\0" {
+            return;
+        }
+
+        let Some((name, value)) = line.split_once(": ") else {
+            warnings.push(SaoriParseWarning::MalformedLine(index, line.to_string()));
+            return;
+        };
+
+        if name == SAORI_PREFIX_SECULITY_LEVEL.trim_end_matches(": ") {
+            if SaoriSecurityLevel::ALL.iter().all(|v| v.to_str() != value) {
+                warnings.push(SaoriParseWarning::UnknownSecurityLevel(
+                    index,
+                    value.to_string(),
+                ));
+            }
+            return;
+        }
+
+        let wrong_case = [
+            SAORI_PREFIX_CHARSET,
+            SAORI_PREFIX_SECULITY_LEVEL,
+            SAORI_PREFIX_SENDER,
+        ]
+        .iter()
+        .map(|prefix| prefix.trim_end_matches(": "))
+        .any(|known| known != name && known.eq_ignore_ascii_case(name))
+            || name
+                .get(..SAORI_PREFIX_ARGUMENT.len())
+                .zip(name.get(SAORI_PREFIX_ARGUMENT.len()..))
+                .is_some_and(|(prefix, index)| {
+                    prefix != SAORI_PREFIX_ARGUMENT
+                        && prefix.eq_ignore_ascii_case(SAORI_PREFIX_ARGUMENT)
+                        && !index.is_empty()
+                        && index.bytes().all(|v| v.is_ascii_digit())
+                });
+        if wrong_case {
+            warnings.push(SaoriParseWarning::HeaderCase(index, name.to_string()));
+        }
+    }
+
+    fn parse_security_level(
+        line: &str,
+        security_level: &mut Option<SaoriSecurityLevel>,
+        policy: SaoriDuplicateHeaderPolicy,
+    ) {
+        if security_level.is_some() && !policy.replaces() {
+            return;
+        }
+        if let Some(body) = line.strip_prefix(SAORI_PREFIX_SECULITY_LEVEL) {
+            *security_level = match body {
+                b if b == SaoriSecurityLevel::Local.to_str() => Some(SaoriSecurityLevel::Local),
+                b if b == SaoriSecurityLevel::External.to_str() => {
+                    Some(SaoriSecurityLevel::External)
+                }
+                b => Some(SaoriSecurityLevel::Other(b.to_string())),
+            };
+        }
+    }
+
+    /// 送られてこなかったArgument*は`None`で埋める。`line_index`はエラーの報告に使う。
+    ///
+    /// 埋める前に番号が`max_arguments`未満であることを確かめるため、大きな番号で大量に確保することはない。
+    fn parse_arguments(
+        line_index: usize,
+        line: &str,
+        arguments: &mut Vec<Option<String>>,
+        max_arguments: usize,
+        options: &SaoriParseOptions,
+    ) -> Result<(), SaoriRequestError> {
+        if let Some(contents) = line.strip_prefix(SAORI_PREFIX_ARGUMENT) {
+            let (index_raw, value) = contents.split_once(": ").ok_or_else(|| {
+                SaoriRequestArgumentError::InvalidSeparator(line_index, line.to_string())
+            })?;
+            let index = index_raw
+                .parse::<usize>()
+                .map_err(|_| SaoriRequestArgumentError::NoIndex(line_index, line.to_string()))?;
+
+            if index >= max_arguments {
+                return Err(SaoriRequestLimitError::TooManyArguments(index).into());
+            }
+            if arguments.len() <= index {
+                arguments.resize(index + 1, None);
+            }
+            if arguments[index].is_none() || options.duplicate_headers.replaces() {
+                arguments[index] = Some(value.to_string())
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_sender(line: &str, sender: &mut Option<String>, policy: SaoriDuplicateHeaderPolicy) {
+        if sender.is_some() && !policy.replaces() {
+            return;
+        }
+        if let Some(body) = line.strip_prefix(SAORI_PREFIX_SENDER) {
+            *sender = Some(body.to_string())
+        }
+    }
+
+    /// 仕様にないヘッダを、名前と値の組として保持する
+    fn parse_extra_header(line: &str, extra_headers: &mut Vec<(String, String)>) {
+        let known = [
+            SAORI_PREFIX_CHARSET,
+            SAORI_PREFIX_SECULITY_LEVEL,
+            SAORI_PREFIX_SENDER,
+            SAORI_PREFIX_ARGUMENT,
+        ]
+        .iter()
+        .any(|prefix| line.starts_with(prefix));
+        if known {
+            return;
+        }
+
+        if let Some((name, value)) = line.split_once(": ") {
+            extra_headers.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    /// 自身をエンコードされた文字バイト列にして返す
+    ///
+    /// SAORIを呼び出す側(ベースウェアやプロキシ)で使う。
+    /// 値に改行か`\0`が含まれている場合は、別のヘッダとして読まれないように
+    /// [`SaoriRequestHeaderError::LineBreak`] を返す。
+    pub fn to_encoded_bytes(&self) -> Result<Vec<u8>, SaoriRequestError> {
+        self.check_line_breaks()?;
+        let mut result = Vec::new();
+        self.charset
+            .encode_to(&self.to_string(), &mut result, |_| None)
+            .map_err(|_| SaoriRequestError::Charset(SaoriRequestCharsetError::EncodeFailed))?;
+        Ok(result)
+    }
+
+    /// `framing`の形式で、自身をエンコードされた文字バイト列にして返す
+    ///
+    /// `framing()`を渡すと、送られてきたときの形式を再現できる。
+    /// 改行が混ざっていた場合はCRLFにし、BOMはUTF-8かUTF-16のときのみ付ける。
+    pub fn to_encoded_bytes_with_framing(
+        &self,
+        framing: &SaoriFraming,
+    ) -> Result<Vec<u8>, SaoriRequestError> {
+        self.check_line_breaks()?;
+        let mut contents = self.to_string();
+        if !framing.has_nul {
+            contents.pop();
+        }
+        if framing.line_ending == SaoriLineEnding::Lf {
+            contents = contents.replace("\r\n", "\n");
+        }
+
+        let mut result = Vec::new();
+        if let Some(bom) = self.charset.bom().filter(|_| framing.has_bom) {
+            result.extend_from_slice(bom);
+        }
+        self.charset
+            .encode_to(&contents, &mut result, |_| None)
+            .map_err(|_| SaoriRequestError::Charset(SaoriRequestCharsetError::EncodeFailed))?;
+        Ok(result)
+    }
+
+    /// 書き出すと改行か`\0`を含んでしまう箇所の、書き出したときの行とヘッダの名前を返す
+    ///
+    /// リクエスト行は、名前を`Command`か`Version`とする。
+    fn line_break_headers(&self) -> Vec<(usize, String)> {
+        let has_line_break = |v: &str| v.contains(['\r', '\n', '\0']);
+        let mut headers = Vec::new();
+        if has_line_break(self.command.to_str()) {
+            headers.push("Command".to_string());
+        }
+        if has_line_break(self.version.to_str()) {
+            headers.push("Version".to_string());
+        }
+
+        // Display と同じ順に、リクエスト行とCharsetの次の行から数える
+        let mut lines: Vec<(String, bool)> = Vec::new();
+        if let Some(sender) = &self.sender {
+            lines.push(("Sender".to_string(), has_line_break(sender)));
+        }
+        if let Some(security_level) = &self.security_level {
+            lines.push((
+                "SecurityLevel".to_string(),
+                has_line_break(security_level.to_str()),
+            ));
+        }
+        lines.extend(
+            self.arguments
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| self.is_argument_present(*index))
+                .map(|(index, argument)| {
+                    (
+                        format!("{}{}", SAORI_PREFIX_ARGUMENT, index),
+                        has_line_break(argument),
+                    )
+                }),
+        );
+        lines.extend(
+            self.extra_headers
+                .iter()
+                .map(|(name, value)| (name.clone(), has_line_break(name) || has_line_break(value))),
+        );
+
+        let mut result: Vec<(usize, String)> = headers.into_iter().map(|v| (0, v)).collect();
+        result.extend(
+            lines
+                .into_iter()
+                .enumerate()
+                .filter(|(_, (_, broken))| *broken)
+                .map(|(index, (name, _))| (index + 2, name)),
+        );
+        result
+    }
+
+    /// 書き出すと改行か`\0`を含んでしまう箇所があれば、最初のものをエラーにする
+    fn check_line_breaks(&self) -> Result<(), SaoriRequestError> {
+        match self.line_break_headers().into_iter().next() {
+            Some((line, name)) => Err(SaoriRequestHeaderError::LineBreak(line, name).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// 比較や重複の除去のための、正規化した自身を返す
+    ///
+    /// CharsetをUTF-8に、仕様にないヘッダを名前と値の順に並べ替え、
+    /// 飛ばされたArgument*を空文字列として埋める。`to_string`などで書き出すと改行はCRLFになる。
+    pub fn normalized(&self) -> SaoriRequest {
+        let mut extra_headers = self.extra_headers.clone();
+        extra_headers.sort();
+
+        SaoriRequest {
+            charset: SaoriCharset::UTF8,
+            command: self.command.clone(),
+            version: self.version.clone(),
+            security_level: self.security_level.clone(),
+            arguments: self.arguments.clone(),
+            arguments_present: vec![true; self.arguments.len()],
+            sender: self.sender.clone(),
+            extra_headers,
+            raw_bytes: None,
+            framing: SaoriFraming::default(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// SAORI/1.0の仕様に沿っているかを確かめ、外れている点を返す
+    ///
+    /// 番号は19xx。Argument*の番号の飛びや、仕様にないヘッダ、コマンドやバージョン、
+    /// SecurityLevel、Charsetで表せない内容、改行や終端の形式を確かめる。
+    ///
+    /// ```
+    /// use saori_interface_rs::*;
+    ///
+    /// let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument1: a\r\n\r\n\0";
+    /// let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+    /// let report = request.validate();
+    ///
+    /// // testing
+    /// assert_eq!(report.max_severity(), Some(SaoriSeverity::Warning));
+    /// assert_eq!(report.iter().next().unwrap().code(), 1901);
+    /// ```
+    pub fn validate(&self) -> SaoriDiagnostics {
+        let mut report = SaoriDiagnostics::new();
+
+        for index in (0..self.arguments.len()).filter(|i| !self.is_argument_present(*i)) {
+            report.push(SaoriDiagnostic::new(
+                SaoriSeverity::Warning,
+                1901,
+                "request.validate.argument_gap",
+                format!("Argument{} is skipped", index),
+            ));
+        }
+        for (name, _) in self.extra_headers.iter() {
+            report.push(SaoriDiagnostic::new(
+                SaoriSeverity::Info,
+                1902,
+                "request.validate.unknown_header",
+                format!("header '{}' is not in SAORI/1.0", name),
+            ));
+        }
+        if let SaoriCommand::Other(command) = &self.command {
+            report.push(SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                1903,
+                "request.validate.unknown_command",
+                format!("command '{}' is not in SAORI/1.0", command),
+            ));
+        }
+        if let SaoriVersion::Other(version) = &self.version {
+            report.push(SaoriDiagnostic::new(
+                SaoriSeverity::Warning,
+                1904,
+                "request.validate.unknown_version",
+                format!("version '{}' is not SAORI/1.0", version),
+            ));
+        }
+        if let Some(SaoriSecurityLevel::Other(level)) = &self.security_level {
+            report.push(SaoriDiagnostic::new(
+                SaoriSeverity::Warning,
+                1905,
+                "request.validate.unknown_security_level",
+                format!("SecurityLevel '{}' is not in SAORI/1.0", level),
+            ));
+        }
+        if self.command == SaoriCommand::GetVersion && !self.arguments.is_empty() {
+            report.push(SaoriDiagnostic::new(
+                SaoriSeverity::Warning,
+                1906,
+                "request.validate.arguments_on_get_version",
+                "GET Version has arguments".to_string(),
+            ));
+        }
+        if self
+            .charset
+            .encode_to(&self.to_string(), &mut Vec::new(), |_| None)
+            .is_err()
+        {
+            report.push(SaoriDiagnostic::new(
+                SaoriSeverity::Error,
+                1907,
+                "request.validate.charset_mismatch",
+                format!("contents cannot be encoded in {}", self.charset),
+            ));
+        }
+        for (line, name) in self.line_break_headers() {
+            report.push(
+                SaoriDiagnostic::new(
+                    SaoriSeverity::Error,
+                    1910,
+                    "request.validate.line_break",
+                    format!("'{}' contains a line break or NUL", name),
+                )
+                .with_location(SaoriLocation::Line(line)),
+            );
+        }
+        if self.framing.line_ending != SaoriLineEnding::CrLf {
+            report.push(SaoriDiagnostic::new(
+                SaoriSeverity::Warning,
+                1908,
+                "request.validate.line_ending",
+                "line endings are not all CRLF".to_string(),
+            ));
+        }
+        if !self.framing.has_nul {
+            report.push(SaoriDiagnostic::new(
+                SaoriSeverity::Warning,
+                1909,
+                "request.validate.missing_nul",
+                "request does not end with NUL".to_string(),
+            ));
+        }
+
+        report
+    }
+
+    /// ログに残すための、値を伏せた表示を返す
+    ///
+    /// ```
+    /// use saori_interface_rs::*;
+    ///
+    /// let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSender: materia\r\nArgument0: secret\r\n\r\n\0";
+    /// let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+    ///
+    /// // testing
+    /// assert_eq!(
+    ///     request.redacted().to_string(),
+    ///     "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSender: ***\r\nArgument0: ***\r\n\r\n\0"
+    /// );
+    /// ```
+    pub fn redacted(&self) -> SaoriRedactedRequest {
+        let mask = |v: &String| {
+            if v.is_empty() {
+                String::new()
+            } else {
+                "***".to_string()
+            }
+        };
+
+        SaoriRedactedRequest {
+            inner: SaoriRequest {
+                charset: self.charset.clone(),
+                command: self.command.clone(),
+                version: self.version.clone(),
+                security_level: self.security_level.clone(),
+                arguments: self.arguments.iter().map(mask).collect(),
+                arguments_present: self.arguments_present.clone(),
+                sender: self.sender.as_ref().map(mask),
+                extra_headers: self
+                    .extra_headers
+                    .iter()
+                    .map(|(k, v)| (k.clone(), mask(v)))
+                    .collect(),
+                raw_bytes: None,
+                framing: self.framing,
+                warnings: Vec::new(),
+            },
+        }
+    }
+
+    /// 人が読むための、複数行に整形した文字列を返す
+    ///
+    /// 読み取り中の警告も併せて表示する。
+    /// ログやデバッグ表示のためのもので、SAORIのリクエストとしては使えない。
+    pub fn to_pretty_string(&self) -> String {
+        let mut rows = vec![
+            ("Version".to_string(), self.version.to_string()),
+            ("Command".to_string(), self.command.to_string()),
+            ("Charset".to_string(), self.charset.to_string()),
+            (
+                "SecurityLevel".to_string(),
+                self.security_level
+                    .as_ref()
+                    .map_or("(none)".to_string(), |v| v.to_string()),
+            ),
+            (
+                "Sender".to_string(),
+                self.sender.clone().unwrap_or("(none)".to_string()),
+            ),
+        ];
+        rows.extend((0..self.arguments.len()).map(|index| {
+            (
+                format!("Argument{}", index),
+                self.argument(index).unwrap_or("(none)").to_string(),
+            )
+        }));
+        rows.extend(self.extra_headers.iter().cloned());
+        rows.extend(self.warnings.iter().map(SaoriDiagnostic::from).map(|v| {
+            (
+                format!("{:?}", v.severity()),
+                format!("[{}] {}", v.code(), v.message()),
+            )
+        }));
+
+        crate::pretty::format_rows("SAORI request", &rows)
+    }
+
+    pub fn charset(&self) -> &SaoriCharset {
+        &self.charset
+    }
+    pub fn command(&self) -> &SaoriCommand {
+        &self.command
+    }
+    pub fn version(&self) -> &SaoriVersion {
+        &self.version
+    }
+    pub fn security_level(&self) -> Option<&SaoriSecurityLevel> {
+        self.security_level.as_ref()
+    }
+    /// バイト列から読み取ったときの警告を返す。それ以外の方法で作ったものでは空。
+    pub fn warnings(&self) -> &[SaoriParseWarning] {
+        &self.warnings
+    }
+    /// Argument*を返す。送られてこなかった番号は空文字列で埋められている。
+    pub fn arguments(&self) -> &[String] {
+        &self.arguments
+    }
+    /// `index`のArgument*が送られてきたかどうか
+    ///
+    /// 空文字列が送られてきた場合は`true`、番号が飛ばされていた場合は`false`。
+    pub fn is_argument_present(&self, index: usize) -> bool {
+        self.arguments_present.get(index).copied().unwrap_or(false)
+    }
+    /// `index`にあるArgument*を返す。送られてこなかった場合は`None`を返す。
+    pub fn argument(&self, index: usize) -> Option<&str> {
+        if !self.is_argument_present(index) {
+            return None;
+        }
+        self.arguments.get(index).map(|v| v.as_str())
+    }
+    /// `index`にあるArgument*を返す。ない場合は`default`を返す。
+    pub fn argument_or<'a>(&'a self, index: usize, default: &'a str) -> &'a str {
+        self.argument(index).unwrap_or(default)
+    }
+    /// 送られてきたArgument*を、番号と値の組で返す。番号が飛ばされた箇所は含まない。
+    pub fn arguments_iter(&self) -> SaoriArgumentsIter<'_> {
+        SaoriArgumentsIter {
+            inner: self
+                .arguments
+                .iter()
+                .zip(self.arguments_present.iter())
+                .enumerate(),
+        }
+    }
+    /// `index`にあるArgument*を`separator`で分けて返す
+    ///
+    /// Argument*がないか空の場合は、空のVecを返す。
+    pub fn argument_split(&self, index: usize, separator: &str) -> Vec<&str> {
+        match self.argument(index) {
+            Some(value) if !value.is_empty() => value.split(separator).collect(),
+            _ => Vec::new(),
+        }
+    }
+    /// `index`にあるArgument*を、バイト値1(`\x01`)区切りのリストとして返す
+    pub fn byte1_list(&self, index: usize) -> Vec<&str> {
+        self.argument_split(index, "\x01")
+    }
+    /// `start`以降のArgument*を、`key=value`または`key: value`として読み取る
+    ///
+    /// 先に現れた区切りで分ける。どちらの形でもないものは含まない。
+    /// 元の値は`arguments`などでそのまま参照できる。
+    ///
+    /// ```
+    /// use saori_interface_rs::*;
+    ///
+    /// let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: open\r\nArgument1: path=C:\\a.txt\r\nArgument2: mode: read\r\n\r\n\0";
+    /// let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+    /// let named = request.named_arguments(1);
+    ///
+    /// // testing
+    /// assert_eq!(named.get("path"), Some("C:\\a.txt"));
+    /// assert_eq!(named.get("mode"), Some("read"));
+    /// ```
+    pub fn named_arguments(&self, start: usize) -> SaoriNamedArguments<'_> {
+        let entries = self
+            .arguments_iter()
+            .filter(|(index, _)| *index >= start)
+            .filter_map(|(_, value)| {
+                let equal = value.split_once('=');
+                let colon = value.split_once(": ");
+                match (equal, colon) {
+                    (Some(e), Some(c)) if c.0.len() < e.0.len() => Some(c),
+                    (Some(e), _) => Some(e),
+                    (None, c) => c,
+                }
+            })
+            .collect();
+        SaoriNamedArguments { entries }
+    }
+    /// `index`にあるArgument*を`T`に変換して返す
+    pub fn argument_as<T: FromStr>(&self, index: usize) -> Result<T, SaoriArgumentParseError> {
+        let value = self
+            .argument(index)
+            .ok_or(SaoriArgumentParseError::Missing(index))?;
+        value
+            .parse()
+            .map_err(|_| SaoriArgumentParseError::Invalid(index, value.to_string()))
+    }
+    /// 先頭からのArgument*をまとめて型に変換して返す
+    ///
+    /// ```
+    /// use saori_interface_rs::*;
+    ///
+    /// let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: name\r\nArgument1: 3\r\n\r\n\0";
+    /// let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+    /// let (name, count): (String, u32) = request.args_as().unwrap();
+    ///
+    /// // testing
+    /// assert_eq!(name, "name");
+    /// assert_eq!(count, 3);
+    /// ```
+    pub fn args_as<T: SaoriFromArguments>(&self) -> Result<T, SaoriArgumentParseError> {
+        T::from_arguments(self)
+    }
+    /// 自身を消費してArgument*を返す
+    pub fn into_arguments(self) -> Vec<String> {
+        self.arguments
+    }
+    pub fn sender(&self) -> Option<&String> {
+        self.sender.as_ref()
+    }
+    /// 自身を消費してSenderを返す
+    pub fn into_sender(self) -> Option<String> {
+        self.sender
+    }
+    /// 仕様にないヘッダを、送られてきた順に返す
+    pub fn extra_headers(&self) -> &[(String, String)] {
+        &self.extra_headers
+    }
+    /// 仕様にないヘッダのうち、名前が`name`の最初のものの値を返す
+    ///
+    /// 名前の大文字と小文字は区別しない。
+    pub fn extra_header(&self, name: &str) -> Option<&str> {
+        self.extra_headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    /// 送られてきたときの改行や終端などの形式
+    pub fn framing(&self) -> &SaoriFraming {
+        &self.framing
+    }
+    /// 受け取ったバイト列を返す。`keep_raw_bytes`で読み取ったときのみ。
+    pub fn raw_bytes(&self) -> Option<&[u8]> {
+        self.raw_bytes.as_deref()
+    }
+    /// 仕様にないヘッダのうち、名前が`name`の最初のものの値を`T`に変換して返す
+    ///
+    /// ヘッダがない場合は`None`を返す。名前の大文字と小文字は区別しない。
+    pub fn header<T: FromStr>(&self, name: &str) -> Option<Result<T, T::Err>> {
+        self.extra_header(name).map(str::parse)
+    }
+    /// 自身を消費して仕様にないヘッダを返す
+    pub fn into_extra_headers(self) -> Vec<(String, String)> {
+        self.extra_headers
+    }
+}
+
+impl<'a> SaoriNamedArguments<'a> {
+    /// 名前が`key`の最初の値を返す
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// 名前と値の組を、送られてきた順に返す
+    pub fn iter(&self) -> std::slice::Iter<'_, (&'a str, &'a str)> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for SaoriNamedArguments<'a> {
+    type Item = (&'a str, &'a str);
+    type IntoIter = std::vec::IntoIter<(&'a str, &'a str)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> Iterator for SaoriArgumentsIter<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .find(|(_, (_, present))| **present)
+            .map(|(index, (value, _))| (index, value.as_str()))
+    }
+}
+
+impl Iterator for SaoriArgumentsIntoIter {
+    type Item = (usize, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .find(|(_, (_, present))| *present)
+            .map(|(index, (value, _))| (index, value))
+    }
+}
+
+impl<'a> IntoIterator for &'a SaoriRequest {
+    type Item = (usize, &'a str);
+    type IntoIter = SaoriArgumentsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.arguments_iter()
+    }
+}
+
+impl IntoIterator for SaoriRequest {
+    type Item = (usize, String);
+    type IntoIter = SaoriArgumentsIntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SaoriArgumentsIntoIter {
+            inner: self
+                .arguments
+                .into_iter()
+                .zip(self.arguments_present)
+                .enumerate(),
+        }
+    }
+}
+
+/// デコード済みの文字列から読み取る
+///
+/// Charsetはヘッダから記録するだけで、デコードし直すことはない。
+impl FromStr for SaoriRequest {
+    type Err = SaoriRequestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let options = SaoriParseOptions::default();
+        let charset = SaoriRequest::read_charset(s.as_bytes(), &options)?;
+        SaoriRequest::parse_contents(s.to_string(), charset, &options).map(|(request, _)| request)
+    }
+}
+
+impl TryFrom<&[u8]> for SaoriRequest {
+    type Error = SaoriRequestError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        SaoriRequest::new(value)
+    }
+}
+
+impl TryFrom<Vec<u8>> for SaoriRequest {
+    type Error = SaoriRequestError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        SaoriRequest::new(&value)
+    }
+}
+
+/// 先頭からのArgument*をまとめて変換できる型
+///
+/// `FromStr`を実装した型の組(12個まで)に実装されている。
+pub trait SaoriFromArguments: Sized {
+    fn from_arguments(request: &SaoriRequest) -> Result<Self, SaoriArgumentParseError>;
+}
+
+macro_rules! impl_saori_from_arguments {
+    ($($t:ident: $index:tt),+) => {
+        impl<$($t: FromStr),+> SaoriFromArguments for ($($t,)+) {
+            fn from_arguments(request: &SaoriRequest) -> Result<Self, SaoriArgumentParseError> {
+                Ok(($(request.argument_as::<$t>($index)?,)+))
+            }
+        }
+    };
+}
+
+impl_saori_from_arguments!(A: 0);
+impl_saori_from_arguments!(A: 0, B: 1);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2, D: 3);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10);
+impl_saori_from_arguments!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7, I: 8, J: 9, K: 10, L: 11);
+
+impl Display for SaoriRedactedRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl std::fmt::Debug for SaoriRedactedRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+impl Display for SaoriRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}\r\n{}{}\r\n",
+            self.command.to_str(),
+            self.version.to_str(),
+            SAORI_PREFIX_CHARSET,
+            self.charset.to_str()
+        )?;
+        if let Some(sender) = &self.sender {
+            write!(f, "{}{}\r\n", SAORI_PREFIX_SENDER, sender)?;
+        }
+        if let Some(security_level) = &self.security_level {
+            write!(
+                f,
+                "{}{}\r\n",
+                SAORI_PREFIX_SECULITY_LEVEL,
+                security_level.to_str()
+            )?;
+        }
+        for (index, argument) in self.arguments.iter().enumerate() {
+            if self.is_argument_present(index) {
+                write!(f, "{}{}: {}\r\n", SAORI_PREFIX_ARGUMENT, index, argument)?;
+            }
+        }
+        for (name, value) in self.extra_headers.iter() {
+            write!(f, "{}: {}\r\n", name, value)?;
+        }
+        write!(f, "\r\n\0")
+    }
+}
+
+impl Default for SaoriRequestBuilder {
+    fn default() -> Self {
+        SaoriRequestBuilder::new()
+    }
+}
+
+impl SaoriRequestBuilder {
+    /// Charsetが UTF-8 、コマンドが EXECUTE の自身を生成する
+    pub fn new() -> SaoriRequestBuilder {
+        SaoriRequestBuilder {
+            charset: SaoriCharset::UTF8,
+            command: SaoriCommand::Execute,
+            version: SaoriVersion::V1_0,
+            security_level: None,
+            arguments: Vec::new(),
+            sender: None,
+            extra_headers: Vec::new(),
+        }
+    }
+
+    pub fn charset(mut self, charset: SaoriCharset) -> SaoriRequestBuilder {
+        self.charset = charset;
+        self
+    }
+
+    pub fn command(mut self, command: SaoriCommand) -> SaoriRequestBuilder {
+        self.command = command;
+        self
+    }
+
+    pub fn version(mut self, version: SaoriVersion) -> SaoriRequestBuilder {
+        self.version = version;
+        self
+    }
+
+    pub fn security_level(mut self, security_level: SaoriSecurityLevel) -> SaoriRequestBuilder {
+        self.security_level = Some(security_level);
+        self
+    }
+
+    pub fn sender(mut self, sender: impl Into<String>) -> SaoriRequestBuilder {
+        self.sender = Some(sender.into());
+        self
+    }
+
+    /// Argument*の末尾に値を追加する
+    pub fn argument(mut self, argument: impl Into<String>) -> SaoriRequestBuilder {
+        self.arguments.push(argument.into());
+        self
+    }
+
+    /// Argument*の末尾に値をまとめて追加する
+    pub fn arguments<I, T>(mut self, arguments: I) -> SaoriRequestBuilder
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.arguments.extend(arguments.into_iter().map(Into::into));
+        self
+    }
+
+    /// 仕様にないヘッダを末尾に追加する
+    pub fn extra_header(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> SaoriRequestBuilder {
+        self.extra_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// 組み立てた自身を返す
+    ///
+    /// 値の改行や`\0`、不正な追加のヘッダの名前を、すべてまとめてエラーとして返す。
+    pub fn build(self) -> Result<SaoriRequest, Vec<SaoriRequestBuildError>> {
+        let mut errors: Vec<SaoriRequestBuildError> = self
+            .extra_headers
+            .iter()
+            .filter(|(name, _)| !is_valid_extra_header_name(name))
+            .map(|(name, _)| SaoriRequestBuildError::InvalidHeader(name.clone()))
+            .collect();
+
+        let request = SaoriRequest {
+            charset: self.charset,
+            command: self.command,
+            version: self.version,
+            security_level: self.security_level,
+            arguments_present: vec![true; self.arguments.len()],
+            arguments: self.arguments,
+            sender: self.sender,
+            extra_headers: self.extra_headers,
+            raw_bytes: None,
+            framing: SaoriFraming::default(),
+            warnings: Vec::new(),
+        };
+        // 追加のヘッダは最後に書き出される。名前が不正なものはInvalidHeaderとしてだけ報告する
+        let first_extra_line = 2
+            + usize::from(request.sender.is_some())
+            + usize::from(request.security_level.is_some())
+            + request.arguments.len();
+        for (line, name) in request.line_break_headers() {
+            if line < first_extra_line || is_valid_extra_header_name(&name) {
+                errors.push(SaoriRequestBuildError::LineBreak(name));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(request)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// SaoriRequestBuilderで追加する、仕様にないヘッダの名前として使えるかどうか
+///
+/// 読み取ったときに仕様にあるヘッダとして扱われる名前は使えない。
+fn is_valid_extra_header_name(name: &str) -> bool {
+    let reserved = [
+        SAORI_PREFIX_CHARSET,
+        SAORI_PREFIX_SECULITY_LEVEL,
+        SAORI_PREFIX_SENDER,
+    ]
+    .iter()
+    .any(|prefix| name.eq_ignore_ascii_case(prefix.trim_end_matches(": ")))
+        || name
+            .get(..SAORI_PREFIX_ARGUMENT.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(SAORI_PREFIX_ARGUMENT));
+    !name.is_empty() && !reserved && name.chars().all(|c| c.is_ascii_graphic() && c != ':')
+}
+
+impl SaoriCharset {
+    /// 対応しているすべてのCharset
+    pub const ALL: [SaoriCharset; 8] = [
+        SaoriCharset::ShiftJIS,
+        SaoriCharset::EucJP,
+        SaoriCharset::UTF8,
+        SaoriCharset::ISO2022JP,
+        SaoriCharset::UTF16LE,
+        SaoriCharset::UTF16BE,
+        SaoriCharset::ASCII,
+        SaoriCharset::Latin1,
+    ];
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            SaoriCharset::ShiftJIS => "Shift_JIS",
+            SaoriCharset::EucJP => "EUC-JP",
+            SaoriCharset::UTF8 => "UTF-8",
+            SaoriCharset::ISO2022JP => "ISO-2022-JP",
+            SaoriCharset::UTF16LE => "UTF-16LE",
+            SaoriCharset::UTF16BE => "UTF-16BE",
+            SaoriCharset::ASCII => "US-ASCII",
+            SaoriCharset::Latin1 => "ISO-8859-1",
+            SaoriCharset::Other(encoding) => encoding.name(),
+        }
+    }
+
+    /// `encoding_rs`のエンコーディングを返す。Shift_JISはWHATWGの定義(Windows-31J相当)になる。
+    ///
+    /// `encoding_rs`にはUS-ASCIIとISO-8859-1がないため、どちらも最も近いwindows-1252を返す
+    /// (このクレートでのエンコードとデコードは、それぞれの範囲で厳密に行う)。
+    pub fn to_encoding(&self) -> &'static Encoding {
+        match self {
+            SaoriCharset::ShiftJIS => SHIFT_JIS,
+            SaoriCharset::EucJP => EUC_JP,
+            SaoriCharset::UTF8 => UTF_8,
+            SaoriCharset::ISO2022JP => ISO_2022_JP,
+            SaoriCharset::UTF16LE => UTF_16LE,
+            SaoriCharset::UTF16BE => UTF_16BE,
+            SaoriCharset::ASCII | SaoriCharset::Latin1 => WINDOWS_1252,
+            SaoriCharset::Other(encoding) => encoding,
+        }
+    }
+
+    /// BOMを返す。BOMを持たないCharsetでは`None`。
+    pub fn bom(&self) -> Option<&'static [u8]> {
+        match self {
+            SaoriCharset::UTF8 => Some(UTF8_BOM),
+            SaoriCharset::UTF16LE => Some(UTF16LE_BOM),
+            SaoriCharset::UTF16BE => Some(UTF16BE_BOM),
+            _ => None,
+        }
+    }
+
+    /// `bytes`をデコードする。表現できないバイト列があった場合は、その位置を返す。
+    pub(crate) fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, usize> {
+        match self {
+            SaoriCharset::ASCII => match bytes.iter().position(|v| !v.is_ascii()) {
+                Some(position) => Err(position),
+                None => std::str::from_utf8(bytes)
+                    .map(Cow::Borrowed)
+                    .map_err(|e| e.valid_up_to()),
+            },
+            SaoriCharset::Latin1 => Ok(Cow::Owned(bytes.iter().map(|v| *v as char).collect())),
+            _ => self
+                .to_encoding()
+                .decode_without_bom_handling_and_without_replacement(bytes)
+                .ok_or_else(|| self.malformed_position(bytes)),
+        }
+    }
+
+    /// デコードできないバイト列の始まる位置
+    ///
+    /// 失敗したときだけ呼ぶため、もう一度先頭から読み直す。
+    fn malformed_position(&self, bytes: &[u8]) -> usize {
+        let mut decoder = self.to_encoding().new_decoder_without_bom_handling();
+        let capacity = decoder
+            .max_utf8_buffer_length_without_replacement(bytes.len())
+            .unwrap_or(bytes.len());
+        let mut output = String::with_capacity(capacity);
+        match decoder.decode_to_string_without_replacement(bytes, &mut output, true) {
+            (DecoderResult::Malformed(malformed, consumed), read) => {
+                read.saturating_sub(malformed as usize + consumed as usize)
+            }
+            _ => bytes.len(),
+        }
+    }
+
+    /// `input`をエンコードして`output`の末尾に書き込む
+    ///
+    /// 表現できない文字があった場合は、`fallback`が返した文字列を代わりにエンコードして続ける。
+    /// `fallback`が`None`を返した(または返した文字列も表現できない)場合は、その文字を返して止める。
+    pub(crate) fn encode_to(
+        &self,
+        input: &str,
+        output: &mut Vec<u8>,
+        mut fallback: impl FnMut(char) -> Option<String>,
+    ) -> Result<(), char> {
+        // encoding_rsはUTF-16へのエンコードに対応していない(UTF-8になる)ため、ここで行う
+        match self {
+            SaoriCharset::UTF16LE => {
+                output.extend(input.encode_utf16().flat_map(u16::to_le_bytes));
+                return Ok(());
+            }
+            SaoriCharset::UTF16BE => {
+                output.extend(input.encode_utf16().flat_map(u16::to_be_bytes));
+                return Ok(());
+            }
+            SaoriCharset::ASCII | SaoriCharset::Latin1 => {
+                let max = if *self == SaoriCharset::ASCII {
+                    0x7F
+                } else {
+                    0xFF
+                };
+                let to_byte = |c: char| u8::try_from(c as u32).ok().filter(|v| *v <= max);
+                for c in input.chars() {
+                    match to_byte(c) {
+                        Some(byte) => output.push(byte),
+                        None => {
+                            let replacement = fallback(c).ok_or(c)?;
+                            for r in replacement.chars() {
+                                output.push(to_byte(r).ok_or(c)?);
+                            }
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let mut encoder = self.to_encoding().new_encoder();
+        let mut src = input;
+        while let Some(c) = encode_until_unmappable(&mut encoder, &mut src, output, true) {
+            let replacement = fallback(c).ok_or(c)?;
+            if encode_until_unmappable(&mut encoder, &mut replacement.as_str(), output, false)
+                .is_some()
+            {
+                return Err(c);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `src`を表現できない文字の手前までエンコードして`output`に書き込み、`src`を残りの部分にする
+///
+/// 表現できない文字があればそれを返す(`src`はその文字の後ろからになる)。
+fn encode_until_unmappable(
+    encoder: &mut Encoder,
+    src: &mut &str,
+    output: &mut Vec<u8>,
+    last: bool,
+) -> Option<char> {
+    loop {
+        output.reserve(
+            encoder
+                .max_buffer_length_from_utf8_without_replacement(src.len())
+                .unwrap_or(src.len()),
+        );
+        let (result, read) = encoder.encode_from_utf8_to_vec_without_replacement(src, output, last);
+        *src = &src[read..];
+        match result {
+            EncoderResult::InputEmpty => return None,
+            EncoderResult::OutputFull => {}
+            EncoderResult::Unmappable(c) => return Some(c),
+        }
+    }
+}
+
+/// `SJIS`や`utf-8`などの別名も受け付ける(WHATWGのラベルに従う)。
+///
+/// WHATWGではwindows-1252になる`us-ascii`や`iso-8859-1`などは、US-ASCIIとISO-8859-1として扱う。
+impl TryFrom<&str> for SaoriCharset {
+    type Error = SaoriRequestCharsetError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        const ASCII_LABELS: [&str; 3] = ["us-ascii", "ascii", "ansi_x3.4-1968"];
+        const LATIN1_LABELS: [&str; 5] = ["iso-8859-1", "iso_8859-1", "latin1", "l1", "iso-ir-100"];
+
+        let label = value.trim().to_ascii_lowercase();
+        if ASCII_LABELS.contains(&label.as_str()) {
+            return Ok(SaoriCharset::ASCII);
+        }
+        if LATIN1_LABELS.contains(&label.as_str()) {
+            return Ok(SaoriCharset::Latin1);
+        }
+
+        match value {
+            v if v == SaoriCharset::ShiftJIS.to_str() => Ok(SaoriCharset::ShiftJIS),
+            v if v == SaoriCharset::EucJP.to_str() => Ok(SaoriCharset::EucJP),
+            v if v == SaoriCharset::UTF8.to_str() => Ok(SaoriCharset::UTF8),
+            v if v == SaoriCharset::ISO2022JP.to_str() => Ok(SaoriCharset::ISO2022JP),
+            v => Encoding::for_label(v.as_bytes())
+                .ok_or(SaoriRequestCharsetError::UnsupportedCharset)
+                .and_then(SaoriCharset::try_from),
+        }
+    }
+}
+
+/// 組み込みのものと同じエンコーディングは、そのCharsetになる。
+///
+/// そのエンコーディング自身でエンコードできないもの(replacementなど)は
+/// [`SaoriRequestCharsetError::UnsupportedCharset`] になる。windows-1252はOtherになる。
+impl TryFrom<&'static Encoding> for SaoriCharset {
+    type Error = SaoriRequestCharsetError;
+
+    fn try_from(encoding: &'static Encoding) -> Result<Self, Self::Error> {
+        if let Some(charset) = SaoriCharset::ALL
+            .into_iter()
+            .filter(|charset| !matches!(charset, SaoriCharset::ASCII | SaoriCharset::Latin1))
+            .find(|charset| charset.to_encoding() == encoding)
+        {
+            Ok(charset)
+        } else if encoding.output_encoding() == encoding {
+            Ok(SaoriCharset::Other(encoding))
+        } else {
+            Err(SaoriRequestCharsetError::UnsupportedCharset)
+        }
+    }
+}
+
+/// [`SaoriCharset::Other`] をエンコーディングの名前で保存・復元する
+#[cfg(feature = "serde")]
+mod serde_encoding {
+    use encoding_rs::Encoding;
+
+    pub fn serialize<S: serde::Serializer>(
+        encoding: &&'static Encoding,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(encoding.name())
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<&'static Encoding, D::Error> {
+        let name = <std::borrow::Cow<'de, str> as serde::Deserialize>::deserialize(deserializer)?;
+        Encoding::for_label(name.as_bytes())
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown encoding: {}", name)))
+    }
+}
+
+impl FromStr for SaoriCharset {
+    type Err = SaoriRequestCharsetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SaoriCharset::try_from(s)
+    }
+}
+
+impl Display for SaoriCharset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+impl SaoriCommand {
+    /// 仕様にあるすべてのコマンド
+    pub const ALL: [SaoriCommand; 2] = [SaoriCommand::Execute, SaoriCommand::GetVersion];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            SaoriCommand::Execute => "EXECUTE",
+            SaoriCommand::GetVersion => "GET Version",
+            SaoriCommand::Other(v) => v,
+        }
+    }
+}
+
+impl FromStr for SaoriCommand {
+    type Err = SaoriRequestVersionLineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SaoriCommand::ALL
+            .into_iter()
+            .find(|v| v.to_str() == s)
+            .ok_or(SaoriRequestVersionLineError::NoCommand)
+    }
+}
+
+impl Display for SaoriCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+impl SaoriVersion {
+    /// 対応しているすべてのバージョン
+    pub const ALL: [SaoriVersion; 1] = [SaoriVersion::V1_0];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            SaoriVersion::V1_0 => "SAORI/1.0",
+            SaoriVersion::Other(v) => v,
+        }
+    }
+
+    /// `SAORI/1.0`のような文字列から、(major, minor)を読み取る
+    fn number(&self) -> Option<(u32, u32)> {
+        let (major, minor) = self
+            .to_str()
+            .strip_prefix(SAORI_VERSION_PREFIX)?
+            .split_once('.')?;
+        Some((major.parse().ok()?, minor.parse().ok()?))
+    }
+
+    /// 自身(リクエストのバージョン)以下で、対応している最も高いバージョンを返す
+    ///
+    /// 見つからない場合は`None`を返す。
+    pub fn negotiate(&self) -> Option<SaoriVersion> {
+        let requested = self.number()?;
+        SaoriVersion::ALL
+            .into_iter()
+            .filter(|v| v.number().is_some_and(|n| n <= requested))
+            .max_by_key(|v| v.number())
+    }
+}
+
+impl FromStr for SaoriVersion {
+    type Err = SaoriRequestVersionLineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SaoriVersion::ALL
+            .into_iter()
+            .find(|v| v.to_str() == s)
+            .ok_or(SaoriRequestVersionLineError::NoVersion)
+    }
+}
+
+impl Display for SaoriVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+impl SaoriSecurityLevel {
+    /// 仕様にあるすべてのSecurityLevel
+    pub const ALL: [SaoriSecurityLevel; 2] =
+        [SaoriSecurityLevel::Local, SaoriSecurityLevel::External];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            SaoriSecurityLevel::Local => "Local",
+            SaoriSecurityLevel::External => "External",
+            SaoriSecurityLevel::Other(v) => v,
+        }
+    }
+}
+
+impl Display for SaoriSecurityLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod saori_request_error {
+        use super::*;
+
+        mod code {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let cases = [
+                    SaoriRequestError::Charset(SaoriRequestCharsetError::DecodeFailed(
+                        0,
+                        String::new(),
+                    )),
+                    SaoriRequestError::Charset(SaoriRequestCharsetError::UnsupportedCharset),
+                    SaoriRequestError::Charset(SaoriRequestCharsetError::EncodeFailed),
+                    SaoriRequestError::VersionLine(SaoriRequestVersionLineError::EmptyRequest),
+                    SaoriRequestError::VersionLine(SaoriRequestVersionLineError::NoVersion),
+                    SaoriRequestError::VersionLine(SaoriRequestVersionLineError::NoCommand),
+                    SaoriRequestError::Argument(SaoriRequestArgumentError::InvalidSeparator(
+                        1,
+                        String::new(),
+                    )),
+                    SaoriRequestError::Argument(SaoriRequestArgumentError::NoIndex(
+                        1,
+                        String::new(),
+                    )),
+                ];
+                let result: Vec<u32> = cases.iter().map(|v| v.code()).collect();
+                assert_eq!(result, vec![1101, 1102, 1103, 1201, 1202, 1203, 1301, 1302]);
+            }
+        }
+    }
+
+    mod saori_request {
+        use super::*;
+
+        mod new {
+
+            use super::*;
+
+            #[test]
+            fn success_when_other_charset() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: GBK\r\nArgument0: 中文\r\n\r\n\0";
+                let case = encoding_rs::GBK.encode(case_raw).0.into_owned();
+                let result = SaoriRequest::new(&case).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::Other(encoding_rs::GBK));
+                assert_eq!(result.argument(0), Some("中文"));
+                assert_eq!(result.to_encoded_bytes().unwrap(), case);
+            }
+
+            #[test]
+            fn success_when_valid_bytes() {
+                let case_raw = "GET Version SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n";
+                let case = SHIFT_JIS.encode(case_raw).0.into_owned();
+                let result = SaoriRequest::new(&case).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::ShiftJIS);
+                assert_eq!(result.command(), &SaoriCommand::GetVersion);
+                assert_eq!(result.version(), &SaoriVersion::V1_0);
+                assert_eq!(result.security_level(), None);
+                assert_eq!(result.arguments(), &Vec::<String>::new());
+                assert_eq!(result.sender(), None);
+            }
+
+            #[test]
+            fn failed_when_invalid_bytes() {
+                let case_raw = "GET SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n";
+                let case = SHIFT_JIS.encode(case_raw).0.into_owned();
+                assert!(SaoriRequest::new(&case).is_err());
+            }
+        }
+
+        mod argument {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: aaa\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(case.argument(0), Some("aaa"));
+                assert_eq!(case.argument(1), None);
+                assert_eq!(case.argument_or(0, "x"), "aaa");
+                assert_eq!(case.argument_or(1, "x"), "x");
+            }
+
+            #[test]
+            fn none_when_skipped() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: \r\nArgument2: c\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(case.arguments().len(), 3);
+                assert!(case.is_argument_present(0));
+                assert!(!case.is_argument_present(1));
+                assert_eq!(case.argument(0), Some(""));
+                assert_eq!(case.argument(1), None);
+                assert_eq!(case.argument_or(1, "x"), "x");
+                assert_eq!(
+                    case.to_string(),
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: \r\nArgument2: c\r\n\r\n\0"
+                );
+            }
+        }
+
+        mod argument_as {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: 12\r\nArgument1: true\r\nArgument2: abc\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(case.argument_as::<i32>(0), Ok(12));
+                assert_eq!(case.argument_as::<bool>(1), Ok(true));
+                assert_eq!(
+                    case.argument_as::<f64>(2),
+                    Err(SaoriArgumentParseError::Invalid(2, "abc".to_string()))
+                );
+                assert_eq!(
+                    case.argument_as::<i32>(3),
+                    Err(SaoriArgumentParseError::Missing(3))
+                );
+            }
+        }
+
+        mod arguments_iter {
+            use super::*;
+
+            #[test]
+            fn skip_when_not_present() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\nArgument2: c\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                let result: Vec<_> = case.arguments_iter().collect();
+                assert_eq!(result, vec![(0, "a"), (2, "c")]);
+                let result: Vec<_> = (&case).into_iter().map(|(_, v)| v).collect();
+                assert_eq!(result.concat(), "ac");
+                let result: Vec<_> = case.into_iter().collect();
+                assert_eq!(result, vec![(0, "a".to_string()), (2, "c".to_string())]);
+            }
+        }
+
+        mod argument_split {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a,b,,c\r\nArgument1: x\x01y\r\nArgument2: \r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(case.argument_split(0, ","), vec!["a", "b", "", "c"]);
+                assert_eq!(case.byte1_list(1), vec!["x", "y"]);
+                assert!(case.byte1_list(2).is_empty());
+                assert!(case.byte1_list(3).is_empty());
+            }
+        }
+
+        mod named_arguments {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a=0\r\nArgument1: b=1\r\nArgument2: plain\r\nArgument3: c: x=y\r\nArgument4: b=2\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                let result = case.named_arguments(1);
+                assert_eq!(
+                    result.iter().copied().collect::<Vec<_>>(),
+                    vec![("b", "1"), ("c", "x=y"), ("b", "2")]
+                );
+                assert_eq!(result.get("b"), Some("1"));
+                assert!(!result.contains_key("a"));
+                assert_eq!(case.argument(2), Some("plain"));
+            }
+        }
+
+        mod args_as {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: name\r\nArgument1: 3\r\nArgument2: false\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                let result: (String, u32, bool) = case.args_as().unwrap();
+                assert_eq!(result, ("name".to_string(), 3, false));
+            }
+
+            #[test]
+            fn failed_when_first_error() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: name\r\nArgument1: x\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(
+                    case.args_as::<(String, u32, bool)>(),
+                    Err(SaoriArgumentParseError::Invalid(1, "x".to_string()))
+                );
+                assert_eq!(
+                    case.args_as::<(String, String, bool)>(),
+                    Err(SaoriArgumentParseError::Missing(2))
+                );
+            }
+        }
+
+        mod into_arguments {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: aaa\r\nArgument1: bbb\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(
+                    case.into_arguments(),
+                    vec!["aaa".to_string(), "bbb".to_string()]
+                );
+            }
+        }
+
+        mod from_str {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\nArgument0: 表示\r\n\r\n\0";
+                let result: SaoriRequest = case.parse().unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::ShiftJIS);
+                assert_eq!(result.argument(0), Some("表示"));
+                assert_eq!(result.to_string(), case);
+            }
+
+            #[test]
+            fn failed_when_unsupported_charset() {
+                let case = "EXECUTE SAORI/1.0\r\nCharset: UTF-7\r\n\r\n\0";
+                assert_eq!(
+                    case.parse::<SaoriRequest>(),
+                    Err(SaoriRequestError::Charset(
+                        SaoriRequestCharsetError::UnsupportedCharset
+                    ))
+                );
+            }
+        }
+
+        mod transcode {
+            use super::*;
+
+            #[test]
+            fn success_when_no_charset() {
+                let case = SHIFT_JIS
+                    .encode("EXECUTE SAORI/1.0\nArgument0: 表示\nX-Extra: 1\n\n\0")
+                    .0
+                    .into_owned();
+                let result = SaoriRequest::transcode(&case, SaoriCharset::UTF8).unwrap();
+                assert_eq!(
+                    String::from_utf8(result).unwrap(),
+                    "EXECUTE SAORI/1.0\nCharset: UTF-8\nArgument0: 表示\nX-Extra: 1\n\n\0"
+                );
+            }
+
+            #[test]
+            fn failed_when_unmappable() {
+                let case = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: \u{1F600}\r\n\r\n\0";
+                assert_eq!(
+                    SaoriRequest::transcode(case.as_bytes(), SaoriCharset::ShiftJIS),
+                    Err(SaoriRequestError::Charset(
+                        SaoriRequestCharsetError::EncodeFailed
+                    ))
+                );
+            }
+        }
+
+        mod from_raw_parts {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case = b"EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\n\r\n\0garbage";
+                let result = unsafe {
+                    SaoriRequest::from_nul_terminated_ptr(case.as_ptr() as *const i8, case.len())
+                }
+                .unwrap();
+                assert_eq!(result.argument(0), Some("a"));
+                let result =
+                    unsafe { SaoriRequest::from_nul_terminated_ptr(case.as_ptr() as *const i8, 8) };
+                assert_eq!(
+                    result,
+                    Err(SaoriRequestError::Framing(
+                        SaoriRequestFramingError::MissingNul
+                    ))
+                );
+            }
+
+            #[test]
+            fn failed_when_null() {
+                let result = unsafe { SaoriRequest::from_raw_parts(std::ptr::null(), 10) };
+                assert_eq!(
+                    result,
+                    Err(SaoriRequestError::VersionLine(
+                        SaoriRequestVersionLineError::EmptyRequest
+                    ))
+                );
+            }
+        }
+
+        mod from_utf16 {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case: Vec<u16> =
+                    "\u{FEFF}EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\nArgument0: 表示\r\n\r\n\0"
+                        .encode_utf16()
+                        .collect();
+                let result = SaoriRequest::from_utf16(&case).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::ShiftJIS);
+                assert_eq!(result.argument(0), Some("表示"));
+                let result = unsafe { SaoriRequest::from_wide_ptr(case.as_ptr(), case.len()) };
+                assert_eq!(result.unwrap().argument(0), Some("表示"));
+            }
+
+            #[test]
+            fn failed_when_unpaired_surrogate() {
+                let case = [0x0045, 0xD800, 0x0045];
+                assert_eq!(
+                    SaoriRequest::from_utf16(&case),
+                    Err(SaoriRequestError::Charset(
+                        SaoriRequestCharsetError::DecodeFailed(
+                            2,
+                            "45 00 [00] D8 45 00".to_string()
+                        )
+                    ))
+                );
+            }
+        }
+
+        mod try_from {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\n\r\n\0";
+                let result = SaoriRequest::try_from(case.as_bytes()).unwrap();
+                assert_eq!(result.argument(0), Some("a"));
+                let result: Result<SaoriRequest, _> = case.as_bytes().to_vec().try_into();
+                assert_eq!(result.unwrap().argument(0), Some("a"));
+            }
+        }
+
+        mod into_sender {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSender: materia\r\nX-Timeout: 10\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(case.into_sender(), Some("materia".to_string()));
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(
+                    case.into_extra_headers(),
+                    vec![("X-Timeout".to_string(), "10".to_string())]
+                );
+            }
+        }
+
+        mod to_encoded_bytes {
+            use super::*;
+
+            #[test]
+            fn success_when_valid_request() {
+                let case = SaoriRequestBuilder::new()
+                    .charset(SaoriCharset::ShiftJIS)
+                    .security_level(SaoriSecurityLevel::Local)
+                    .sender("materia")
+                    .arguments(["あ", "b"])
+                    .build()
+                    .unwrap();
+                let result = case.to_encoded_bytes().unwrap();
+                let expect_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\nSender: materia\r\nSecurityLevel: Local\r\nArgument0: あ\r\nArgument1: b\r\n\r\n\0";
+                let expect = SHIFT_JIS.encode(expect_raw).0.into_owned();
+                assert_eq!(result, expect);
+                assert_eq!(SaoriRequest::new(&result).unwrap(), case);
+            }
+
+            #[test]
+            fn failed_when_unencodable() {
+                let case = SaoriRequestBuilder::new()
+                    .charset(SaoriCharset::ShiftJIS)
+                    .argument("\u{1F600}")
+                    .build()
+                    .unwrap();
+                assert_eq!(
+                    case.to_encoded_bytes(),
+                    Err(SaoriRequestError::Charset(
+                        SaoriRequestCharsetError::EncodeFailed
+                    ))
+                );
+            }
+
+            #[test]
+            fn failed_when_nul_in_value() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSender: s\r\nArgument0: a\0b\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                let expect = Err(SaoriRequestError::Header(
+                    SaoriRequestHeaderError::LineBreak(3, "Argument0".to_string()),
+                ));
+                assert_eq!(case.to_encoded_bytes(), expect);
+                assert_eq!(
+                    case.to_encoded_bytes_with_framing(&SaoriFraming::default()),
+                    expect
+                );
+            }
+        }
+
+        mod framing {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "\u{FEFF}EXECUTE SAORI/1.0\nCharset: UTF-8\nArgument0: a\n\n";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(
+                    case.framing(),
+                    &SaoriFraming::new(SaoriLineEnding::Lf, false, true)
+                );
+                assert_eq!(
+                    case.to_encoded_bytes_with_framing(case.framing()).unwrap(),
+                    case_raw.as_bytes()
+                );
+            }
+
+            #[test]
+            fn checking_mixed() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(
+                    case.framing(),
+                    &SaoriFraming::new(SaoriLineEnding::Mixed, true, false)
+                );
+            }
+        }
+
+        mod validate {
+            use super::*;
+
+            #[test]
+            fn empty_when_conforming() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSecurityLevel: Local\r\nArgument0: a\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert!(case.validate().is_empty());
+            }
+
+            #[test]
+            fn checking_value() {
+                let case = SaoriRequestBuilder::new()
+                    .charset(SaoriCharset::ShiftJIS)
+                    .command(SaoriCommand::GetVersion)
+                    .argument("\u{1F600}")
+                    .extra_header("X-Extra", "1")
+                    .build()
+                    .unwrap();
+                let result: Vec<u32> = case.validate().iter().map(|v| v.code()).collect();
+                assert_eq!(result, vec![1902, 1906, 1907]);
+                assert!(case.validate().has_errors());
+            }
+
+            #[test]
+            fn line_break_is_not_charset_mismatch() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\0b\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                let report = case.validate();
+                let result: Vec<u32> = report.iter().map(|v| v.code()).collect();
+                assert_eq!(result, vec![1910]);
+                assert_eq!(
+                    report.iter().next().unwrap().location(),
+                    Some(&SaoriLocation::Line(2))
+                );
+            }
+        }
+
+        mod redacted {
+            use super::*;
+
+            #[test]
+            fn checking_debug() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: secret\r\nArgument1: \r\nX-Path: C:\\secret\r\n\r\n\0";
+                let options = SaoriParseOptions::new().keep_raw_bytes(true);
+                let case = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                let result = format!("{:?}", case.redacted());
+                assert!(!result.contains("secret"));
+                assert!(result.contains("X-Path"));
+                assert_eq!(
+                    case.redacted().to_string(),
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: ***\r\nArgument1: \r\nX-Path: ***\r\n\r\n\0"
+                );
+            }
+        }
+
+        mod normalized {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\nCharset: Shift_JIS\nX-B: 2\nArgument2: c\nX-A: 1\nArgument0: a\n\n";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                let result = case.normalized();
+                assert_eq!(
+                    result.to_string(),
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\nArgument1: \r\nArgument2: c\r\nX-A: 1\r\nX-B: 2\r\n\r\n\0"
+                );
+                let other_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nX-A: 1\r\nArgument0: a\r\nArgument1: \r\nX-B: 2\r\nArgument2: c\r\n\r\n\0";
+                let other = SaoriRequest::new(other_raw.as_bytes()).unwrap();
+                assert_eq!(other.normalized(), result);
+            }
+        }
+
+        mod to_pretty_string {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSender: materia\r\nArgument0: a\r\nArgument1: b\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                let expect = "SAORI request
+  Version       : SAORI/1.0
+  Command       : EXECUTE
+  Charset       : UTF-8
+  SecurityLevel : (none)
+  Sender        : materia
+  Argument0     : a
+  Argument1     : b";
+                assert_eq!(case.to_pretty_string(), expect);
+            }
+
+            #[test]
+            fn checking_warnings() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSecurityLevel: Remote\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                let expect = "SAORI request
+  Version       : SAORI/1.0
+  Command       : EXECUTE
+  Charset       : UTF-8
+  SecurityLevel : Remote
+  Sender        : (none)
+  Warning       : [1701] unknown SecurityLevel 'Remote'";
+                assert_eq!(case.to_pretty_string(), expect);
+            }
+        }
+
+        mod read_contents_and_charset {
+
+            use super::*;
+
+            #[test]
+            fn success_when_valid_bytes() {
+                let case_raw = "GET Version SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n";
+                let case = SHIFT_JIS.encode(case_raw).0.into_owned();
+                let (contents, charset) =
+                    SaoriRequest::read_contents_and_charset(&case, &SaoriParseOptions::default())
+                        .unwrap();
+                assert_eq!(contents.as_str(), case_raw);
+                assert_eq!(charset, SaoriCharset::ShiftJIS);
+            }
+
+            #[test]
+            fn failed_when_invalid_bytes() {
+                let case_raw =
+                    "EXECUTE SHIORI/1.0\r\nCharset: UTF-8\r\nArgument0: あいうえお\r\n\r\n";
+                let case = SHIFT_JIS.encode(case_raw).0.into_owned();
+                assert!(SaoriRequest::read_contents_and_charset(
+                    &case,
+                    &SaoriParseOptions::default()
+                )
+                .is_err());
+            }
+
+            #[test]
+            fn checking_value_of_decode_error() {
+                let case = b"EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\xFFb\r\n\r\n";
+                assert_eq!(
+                    SaoriRequest::read_contents_and_charset(case, &SaoriParseOptions::default()),
+                    Err(SaoriRequestCharsetError::DecodeFailed(
+                        47,
+                        "30 3A 20 61 [FF] 62 0D 0A 0D".to_string()
+                    ))
+                );
+
+                let case = [b"\xEF\xBB\xBF".as_slice(), case].concat();
+                assert_eq!(
+                    SaoriRequest::read_contents_and_charset(&case, &SaoriParseOptions::default()),
+                    Err(SaoriRequestCharsetError::DecodeFailed(
+                        50,
+                        "30 3A 20 61 [FF] 62 0D 0A 0D".to_string()
+                    ))
+                );
+            }
+
+            #[test]
+            fn checking_value_of_decode_error_when_shift_jis() {
+                let case = b"EXECUTE SAORI/1.0\r\nArgument0: \x82\r\n";
+                assert_eq!(
+                    SaoriRequest::read_contents_and_charset(case, &SaoriParseOptions::default()),
+                    Err(SaoriRequestCharsetError::DecodeFailed(
+                        30,
+                        "74 30 3A 20 [82] 0D 0A".to_string()
+                    ))
+                );
+            }
+        }
+
+        mod new_with_diagnostics {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\ngarbage\r\nSecurityLevel: Remote\r\n\r\n\0";
+                let (_, diagnostics) = SaoriRequest::new_with_diagnostics(
+                    case_raw.as_bytes(),
+                    &SaoriParseOptions::new(),
+                )
+                .unwrap();
+                let codes: Vec<u32> = diagnostics.iter().map(|d| d.code()).collect();
+                assert_eq!(codes, vec![1702, 1701]);
+                assert_eq!(
+                    diagnostics.iter().next().unwrap().location(),
+                    Some(&SaoriLocation::Line(2))
+                );
+                assert_eq!(diagnostics.max_severity(), Some(SaoriSeverity::Warning));
+            }
+
+            #[test]
+            fn empty_when_no_warnings() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\n\r\n\0";
+                let (_, diagnostics) = SaoriRequest::new_with_diagnostics(
+                    case_raw.as_bytes(),
+                    &SaoriParseOptions::new(),
+                )
+                .unwrap();
+                assert!(diagnostics.is_empty());
+            }
+        }
+
+        mod new_with_warnings {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\ngarbage\r\nsender: materia\r\nargument0: a\r\nSecurityLevel: Local\r\n\r\n\0";
+                let (result, warnings) =
+                    SaoriRequest::new_with_warnings(case_raw.as_bytes(), &SaoriParseOptions::new())
+                        .unwrap();
+                assert!(result.sender().is_none());
+                assert_eq!(
+                    warnings,
+                    vec![
+                        SaoriParseWarning::MalformedLine(2, "garbage".to_string()),
+                        SaoriParseWarning::HeaderCase(3, "sender".to_string()),
+                        SaoriParseWarning::HeaderCase(4, "argument0".to_string()),
+                    ]
+                );
+                assert_eq!(warnings[0].code(), 1702);
+                assert_eq!(warnings[1].line(), 3);
+            }
+
+            #[test]
+            fn empty_when_valid() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\nX-Extra: 1\r\n\r\n\0";
+                let (_, warnings) =
+                    SaoriRequest::new_with_warnings(case_raw.as_bytes(), &SaoriParseOptions::new())
+                        .unwrap();
+                assert!(warnings.is_empty());
+            }
+        }
+
+        mod default_charset {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nArgument0: あ\r\n\r\n\0";
+                assert!(SaoriRequest::new(case_raw.as_bytes()).is_err());
+                let options = SaoriParseOptions::new().default_charset(SaoriCharset::UTF8);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::UTF8);
+                assert_eq!(result.argument(0), Some("あ"));
+            }
+        }
+
+        mod detect_charset {
+            use super::*;
+
+            #[test]
+            fn success_when_utf8_declared_as_shift_jis() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\nArgument0: あ\r\n\r\n\0";
+                assert!(SaoriRequest::new(case_raw.as_bytes()).is_err());
+                let options = SaoriParseOptions::new().detect_charset(true);
+                let (result, warnings) =
+                    SaoriRequest::new_with_warnings(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::UTF8);
+                assert_eq!(result.argument(0), Some("あ"));
+                assert_eq!(
+                    warnings,
+                    vec![SaoriParseWarning::CharsetCorrected(
+                        1,
+                        SaoriCharset::ShiftJIS,
+                        SaoriCharset::UTF8
+                    )]
+                );
+                assert_eq!(warnings[0].code(), 1704);
+            }
+
+            #[test]
+            fn success_when_shift_jis_declared_as_utf8() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nArgument0: あいう\r\nCharset: UTF-8\r\n\r\n\0";
+                let case = SHIFT_JIS.encode(case_raw).0.into_owned();
+                let options = SaoriParseOptions::new().detect_charset(true);
+                let (result, warnings) = SaoriRequest::new_with_warnings(&case, &options).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::ShiftJIS);
+                assert_eq!(result.argument(0), Some("あいう"));
+                assert_eq!(
+                    warnings,
+                    vec![SaoriParseWarning::CharsetCorrected(
+                        2,
+                        SaoriCharset::UTF8,
+                        SaoriCharset::ShiftJIS
+                    )]
+                );
+            }
+
+            #[test]
+            fn success_when_euc_jp_declared_as_utf8() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: あいう\r\n\r\n\0";
+                let case = EUC_JP.encode(case_raw).0.into_owned();
+                let options = SaoriParseOptions::new().detect_charset(true);
+                let result = SaoriRequest::new_with_options(&case, &options).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::EucJP);
+                assert_eq!(result.argument(0), Some("あいう"));
+            }
+
+            #[test]
+            fn keep_declared_when_readable() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\nArgument0: あいう\r\n\r\n\0";
+                let case = SHIFT_JIS.encode(case_raw).0.into_owned();
+                let options = SaoriParseOptions::new().detect_charset(true);
+                let (result, warnings) = SaoriRequest::new_with_warnings(&case, &options).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::ShiftJIS);
+                assert!(warnings.is_empty());
+            }
+        }
+
+        mod strip_bom {
+            use super::*;
+
+            #[test]
+            fn success_when_utf8_bom() {
+                let case_raw =
+                    "\u{FEFF}EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\nArgument0: あ\r\n\r\n\0";
+                let result = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::UTF8);
+                assert_eq!(result.command(), &SaoriCommand::Execute);
+                assert_eq!(result.argument(0), Some("あ"));
+            }
+
+            #[test]
+            fn success_when_utf16_bom() {
+                let case = b"\xFF\xFEE\0X\0";
+                assert_eq!(
+                    SaoriRequest::strip_bom(case),
+                    (&b"E\0X\0"[..], Some(SaoriCharset::UTF16LE))
+                );
+                let case = b"\xFE\xFF\0E\0X";
+                assert_eq!(
+                    SaoriRequest::strip_bom(case),
+                    (&b"\0E\0X"[..], Some(SaoriCharset::UTF16BE))
+                );
+            }
+
+            #[test]
+            fn success_when_utf16le() {
+                let case_raw =
+                    "\u{FEFF}EXECUTE SAORI/1.0\r\nCharset: UTF-16LE\r\nArgument0: あ\r\n\r\n\0";
+                let mut case = Vec::new();
+                SaoriCharset::UTF16LE
+                    .encode_to(case_raw, &mut case, |_| None)
+                    .unwrap();
+                assert!(case.ends_with(b"\0\0"));
+                let result = SaoriRequest::new(&case).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::UTF16LE);
+                assert_eq!(result.argument(0), Some("あ"));
+                assert!(result.framing().has_bom());
+                assert_eq!(
+                    result
+                        .to_encoded_bytes_with_framing(result.framing())
+                        .unwrap(),
+                    case
+                );
+            }
+
+            #[test]
+            fn success_when_utf16be_without_bom() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-16BE\r\nArgument0: あ\r\n\r\n\0";
+                let mut case = Vec::new();
+                SaoriCharset::UTF16BE
+                    .encode_to(case_raw, &mut case, |_| None)
+                    .unwrap();
+                let result = SaoriRequest::new(&case).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::UTF16BE);
+                assert_eq!(result.argument(0), Some("あ"));
+                assert!(!result.framing().has_bom());
+            }
+        }
+
+        mod find_charset_label {
+
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nArgument0: 表示\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let case = SHIFT_JIS.encode(case_raw).0.into_owned();
+                assert_eq!(
+                    SaoriRequest::find_charset_label(&case, &SaoriParseOptions::default()),
+                    Some("Shift_JIS".as_bytes())
+                );
+            }
+
+            #[test]
+            fn none_when_no_charset() {
+                let case = "EXECUTE SAORI/1.0\r\n Charset: UTF-8\r\n\r\n\0".as_bytes();
+                assert_eq!(
+                    SaoriRequest::find_charset_label(case, &SaoriParseOptions::default()),
+                    None
+                );
+                assert_eq!(
+                    SaoriRequest::find_charset_label(
+                        case,
+                        &SaoriParseOptions::new().trim_whitespace(true)
+                    ),
+                    Some("UTF-8".as_bytes())
+                );
+            }
+        }
+
+        mod parse_versioni_and_command {
+            use super::*;
+
+            #[test]
+            fn success_when_valid_str_get_version() {
+                let case = Some("GET Version SAORI/1.0");
+                let (command, version) =
+                    SaoriRequest::parse_version_and_command(case, &SaoriParseOptions::default())
+                        .unwrap();
+                assert_eq!(command, SaoriCommand::GetVersion);
+                assert_eq!(version, SaoriVersion::V1_0);
+            }
+
+            #[test]
+            fn success_when_valid_str_execute() {
+                let case = Some("EXECUTE SAORI/1.0");
+                let (command, version) =
+                    SaoriRequest::parse_version_and_command(case, &SaoriParseOptions::default())
+                        .unwrap();
+                assert_eq!(command, SaoriCommand::Execute);
+                assert_eq!(version, SaoriVersion::V1_0);
+            }
+
+            #[test]
+            fn failed_when_invalid_command() {
+                let case = Some("SOMETHINGWRONG SAORI/1.0");
+                assert!(SaoriRequest::parse_version_and_command(
+                    case,
+                    &SaoriParseOptions::default()
+                )
+                .is_err());
+            }
+
+            #[test]
+            fn success_when_unknown_command_accepted() {
+                let case = Some("NOTIFY OnBoot SAORI/1.0");
+                let options = SaoriParseOptions::new().accept_unknown_command(true);
+                let (command, version) =
+                    SaoriRequest::parse_version_and_command(case, &options).unwrap();
+                assert_eq!(command, SaoriCommand::Other("NOTIFY OnBoot".to_string()));
+                assert_eq!(version, SaoriVersion::V1_0);
+            }
+
+            #[test]
+            fn failed_when_unknown_command_accepted_and_no_command() {
+                let case = Some(" SAORI/1.0");
+                let options = SaoriParseOptions::new().accept_unknown_command(true);
+                assert!(SaoriRequest::parse_version_and_command(case, &options).is_err());
+            }
+
+            #[test]
+            fn success_when_unknown_version_accepted() {
+                let case = Some("EXECUTE SAORI/1.1");
+                let options = SaoriParseOptions::new().accept_unknown_version(true);
+                let (_, version) = SaoriRequest::parse_version_and_command(case, &options).unwrap();
+                assert_eq!(version, SaoriVersion::Other("SAORI/1.1".to_string()));
+            }
+
+            #[test]
+            fn failed_when_invalid_version() {
+                let case = Some("EXECUTE SAORI1.0");
+                assert!(SaoriRequest::parse_version_and_command(
+                    case,
+                    &SaoriParseOptions::default()
+                )
+                .is_err());
+            }
+
+            #[test]
             fn failed_when_none() {
                 let case = None;
-                assert!(SaoriRequest::parse_version_and_command(case).is_err());
+                assert!(SaoriRequest::parse_version_and_command(
+                    case,
+                    &SaoriParseOptions::default()
+                )
+                .is_err());
+            }
+        }
+
+        mod new_with_options {
+            use super::*;
+
+            #[test]
+            fn success_when_strict_and_valid() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSender: materia\r\nArgument0: a\r\nArgument1: b\r\n\r\n\0";
+                let options = SaoriParseOptions::new().strict(true);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.arguments(), &["a".to_string(), "b".to_string()]);
+            }
+
+            #[test]
+            fn success_when_strict_and_no_empty_line_before_terminator() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\n\0";
+                let options = SaoriParseOptions::new().strict(true);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.arguments(), &["a".to_string()]);
+            }
+
+            #[test]
+            fn success_when_not_strict_and_unknown_header() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nX-Extra: 1\r\n\r\n\0";
+                let options = SaoriParseOptions::new();
+                assert!(SaoriRequest::new_with_options(case_raw.as_bytes(), &options).is_ok());
+            }
+
+            #[test]
+            fn checking_raw_bytes() {
+                let case_raw = "EXECUTE SAORI/1.0\nCharset: UTF-8\n\n\0";
+                let result = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert!(result.raw_bytes().is_none());
+                let options = SaoriParseOptions::new().keep_raw_bytes(true);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.raw_bytes(), Some(case_raw.as_bytes()));
+            }
+
+            #[test]
+            fn success_when_duplicate_and_last_wins() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSender: a\r\nSender: b\r\n\r\n\0";
+                let result = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(result.sender(), Some(&"b".to_string()));
+            }
+
+            #[test]
+            fn success_when_duplicate_and_first_wins() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\nCharset: UTF-8\r\nSender: a\r\nSender: b\r\nSecurityLevel: Local\r\nSecurityLevel: External\r\nArgument0: x\r\nArgument0: y\r\n\r\n\0";
+                let result = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::UTF8);
+                assert_eq!(result.argument(0), Some("y"));
+                let options = SaoriParseOptions::new()
+                    .duplicate_headers(SaoriDuplicateHeaderPolicy::FirstWins);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::ShiftJIS);
+                assert_eq!(result.sender(), Some(&"a".to_string()));
+                assert_eq!(result.security_level(), Some(&SaoriSecurityLevel::Local));
+                assert_eq!(result.argument(0), Some("x"));
+            }
+
+            #[test]
+            fn failed_when_duplicate_and_policy_error() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nX-Extra: 1\r\nSender: a\r\nSender: b\r\n\r\n\0";
+                let options =
+                    SaoriParseOptions::new().duplicate_headers(SaoriDuplicateHeaderPolicy::Error);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options);
+                assert_eq!(
+                    result,
+                    Err(SaoriRequestError::Header(
+                        SaoriRequestHeaderError::Duplicate(4, "Sender".to_string())
+                    ))
+                );
+            }
+
+            #[test]
+            fn success_when_duplicate_unknown_and_policy_error() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nX-Foo: 1\r\nX-Foo: 2\r\nSender: a\r\n\r\n\0";
+                let options =
+                    SaoriParseOptions::new().duplicate_headers(SaoriDuplicateHeaderPolicy::Error);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.sender(), Some(&"a".to_string()));
+                assert_eq!(
+                    result.extra_headers(),
+                    &[
+                        ("X-Foo".to_string(), "1".to_string()),
+                        ("X-Foo".to_string(), "2".to_string())
+                    ]
+                );
+            }
+
+            #[test]
+            fn failed_when_duplicate_charset_and_policy_error() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nCharset: UTF-8\r\n\r\n\0";
+                let options =
+                    SaoriParseOptions::new().duplicate_headers(SaoriDuplicateHeaderPolicy::Error);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options);
+                assert_eq!(
+                    result,
+                    Err(SaoriRequestError::Header(
+                        SaoriRequestHeaderError::Duplicate(2, "Charset".to_string())
+                    ))
+                );
+            }
+
+            #[test]
+            fn success_when_trim_whitespace() {
+                let case_raw = "EXECUTE SAORI/1.0 \nCharset: UTF-8 \n SecurityLevel: Local\t\n\n";
+                assert!(SaoriRequest::new(case_raw.as_bytes()).is_err());
+                let options = SaoriParseOptions::new().trim_whitespace(true);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::UTF8);
+                assert_eq!(result.security_level(), Some(&SaoriSecurityLevel::Local));
+            }
+
+            #[test]
+            fn failed_when_strict_and_unknown_header() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nX-Extra: 1\r\n\r\n\0";
+                let options = SaoriParseOptions::new().strict(true);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options);
+                assert_eq!(
+                    result,
+                    Err(SaoriRequestError::Header(SaoriRequestHeaderError::Unknown(
+                        2,
+                        "X-Extra".to_string()
+                    )))
+                );
+            }
+
+            #[test]
+            fn failed_when_strict_and_duplicate_header() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\nArgument00: b\r\n\r\n\0";
+                let options = SaoriParseOptions::new().strict(true);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options);
+                assert_eq!(
+                    result,
+                    Err(SaoriRequestError::Header(
+                        SaoriRequestHeaderError::Duplicate(3, "Argument0".to_string())
+                    ))
+                );
+            }
+        }
+
+        mod unfold_lines {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\n b\r\n\tc\r\nArgument1: d\r\n\r\n\0";
+                let result = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(result.argument(0), Some("a"));
+                let options = SaoriParseOptions::new().unfold_lines(true);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.argument(0), Some("a b c"));
+                assert_eq!(result.argument(1), Some("d"));
+            }
+
+            #[test]
+            fn nothing_when_after_request_line() {
+                let case = "EXECUTE SAORI/1.0\r\n Charset: UTF-8\r\n\r\n\0";
+                let result = SaoriRequest::unfold_lines(case, true);
+                assert_eq!(
+                    result,
+                    vec!["EXECUTE SAORI/1.0", " Charset: UTF-8", "", "\0"]
+                );
+            }
+
+            #[test]
+            fn warning_when_orphan_continuation() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\n SecurityLevel: External\r\nCharset: UTF-8\r\n\r\n\0";
+                let options = SaoriParseOptions::new().unfold_lines(true);
+                let (result, warnings) =
+                    SaoriRequest::new_with_warnings(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.security_level(), None);
+                assert!(result.extra_headers().is_empty());
+                assert_eq!(
+                    warnings,
+                    vec![SaoriParseWarning::OrphanContinuation(
+                        1,
+                        " SecurityLevel: External".to_string()
+                    )]
+                );
+            }
+
+            #[test]
+            fn keep_header_boundaries_when_forwarded() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSecurityLevel: Local\r\nArgument0: a\r\n SecurityLevel: External\r\n\r\n\0";
+                let options = SaoriParseOptions::new().unfold_lines(true);
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options).unwrap();
+                assert_eq!(result.argument(0), Some("a SecurityLevel: External"));
+                let forwarded = SaoriRequest::new(&result.to_encoded_bytes().unwrap()).unwrap();
+                assert_eq!(forwarded.security_level(), Some(&SaoriSecurityLevel::Local));
+                assert_eq!(forwarded, result);
+            }
+        }
+
+        mod check_control_characters {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case = "EXECUTE SAORI/1.0\r\nArgument0: a\x01b\0c\r\n\r\n\0".to_string();
+                assert_eq!(
+                    SaoriRequest::check_control_characters(
+                        case.clone(),
+                        SaoriControlCharacterPolicy::Allow
+                    ),
+                    Ok(case.clone())
+                );
+                assert_eq!(
+                    SaoriRequest::check_control_characters(
+                        case.clone(),
+                        SaoriControlCharacterPolicy::Reject
+                    ),
+                    Err(SaoriRequestError::Framing(
+                        SaoriRequestFramingError::ControlCharacter(1, '\x01')
+                    ))
+                );
+                assert_eq!(
+                    SaoriRequest::check_control_characters(
+                        case,
+                        SaoriControlCharacterPolicy::Strip
+                    ),
+                    Ok("EXECUTE SAORI/1.0\r\nArgument0: abc\r\n\r\n\0".to_string())
+                );
+            }
+
+            #[test]
+            fn success_when_only_terminator() {
+                let case = "EXECUTE SAORI/1.0\r\nArgument0: a\tb\r\n\r\n\0".to_string();
+                assert!(SaoriRequest::check_control_characters(
+                    case,
+                    SaoriControlCharacterPolicy::Reject
+                )
+                .is_ok());
+            }
+        }
+
+        mod limits {
+            use super::*;
+
+            #[test]
+            fn failed_when_too_large() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let options = SaoriParseOptions::new().limits(SaoriLimits::new().max_bytes(16));
+                assert_eq!(
+                    SaoriRequest::new_with_options(case_raw.as_bytes(), &options),
+                    Err(SaoriRequestError::Limit(SaoriRequestLimitError::TooLarge(
+                        case_raw.len()
+                    )))
+                );
+            }
+
+            #[test]
+            fn failed_when_line_too_long() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: aaaaaaaaaa\r\n\r\n\0";
+                let options =
+                    SaoriParseOptions::new().limits(SaoriLimits::new().max_line_length(20));
+                let result = SaoriRequest::new_with_options(case_raw.as_bytes(), &options);
+                assert_eq!(
+                    result,
+                    Err(SaoriRequestError::Limit(
+                        SaoriRequestLimitError::LineTooLong(2, 21)
+                    ))
+                );
+            }
+        }
+
+        mod check_framing {
+            use super::*;
+
+            #[test]
+            fn success_when_lenient() {
+                let case = "EXECUTE SAORI/1.0\nCharset: UTF-8\n\n";
+                assert!(SaoriRequest::check_framing(case, &SaoriParseOptions::default()).is_ok());
+            }
+
+            #[test]
+            fn failed_when_bare_line_feed() {
+                let case = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\n\r\n\0";
+                let options = SaoriParseOptions::new().require_crlf(true);
+                assert_eq!(
+                    SaoriRequest::check_framing(case, &options),
+                    Err(SaoriRequestError::Framing(
+                        SaoriRequestFramingError::BareLineFeed(1)
+                    ))
+                );
+            }
+
+            #[test]
+            fn failed_when_missing_nul() {
+                let case = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n";
+                let options = SaoriParseOptions::new().require_nul(true);
+                assert_eq!(
+                    SaoriRequest::check_framing(case, &options),
+                    Err(SaoriRequestError::Framing(
+                        SaoriRequestFramingError::MissingNul
+                    ))
+                );
             }
         }
 
@@ -415,7 +3882,7 @@ mod tests {
             fn execute_when_valid_str_local() {
                 let case = "SecurityLevel: Local";
                 let mut security_level = None;
-                SaoriRequest::parse_security_level(case, &mut security_level);
+                SaoriRequest::parse_security_level(case, &mut security_level, Default::default());
                 assert_eq!(security_level, Some(SaoriSecurityLevel::Local));
             }
 
@@ -423,15 +3890,26 @@ mod tests {
             fn execute_when_valid_str_external() {
                 let case = "SecurityLevel: External";
                 let mut security_level = None;
-                SaoriRequest::parse_security_level(case, &mut security_level);
+                SaoriRequest::parse_security_level(case, &mut security_level, Default::default());
                 assert_eq!(security_level, Some(SaoriSecurityLevel::External));
             }
 
+            #[test]
+            fn execute_when_unknown_str() {
+                let case = "SecurityLevel: Remote";
+                let mut security_level = None;
+                SaoriRequest::parse_security_level(case, &mut security_level, Default::default());
+                assert_eq!(
+                    security_level,
+                    Some(SaoriSecurityLevel::Other("Remote".to_string()))
+                );
+            }
+
             #[test]
             fn nothing_when_invalid_str() {
                 let case = "Argument2: aaa";
                 let mut security_level = None;
-                SaoriRequest::parse_security_level(case, &mut security_level);
+                SaoriRequest::parse_security_level(case, &mut security_level, Default::default());
                 assert!(security_level.is_none());
             }
         }
@@ -442,22 +3920,36 @@ mod tests {
             #[test]
             fn success_when_valid_str_inner() {
                 let case = "Argument2: あああ";
-                let mut arguments = vec!["".to_string(), "".to_string(), "".to_string()];
-                SaoriRequest::parse_arguments(case, &mut arguments).unwrap();
+                let mut arguments = vec![Some("".to_string()), None, None];
+                SaoriRequest::parse_arguments(
+                    1,
+                    case,
+                    &mut arguments,
+                    usize::MAX,
+                    &SaoriParseOptions::default(),
+                )
+                .unwrap();
                 assert_eq!(
                     arguments,
-                    vec!["".to_string(), "".to_string(), "あああ".to_string(),]
+                    vec![Some("".to_string()), None, Some("あああ".to_string())]
                 );
             }
 
             #[test]
             fn success_when_valid_str_outer() {
                 let case = "Argument2: あああ";
-                let mut arguments = vec!["".to_string()];
-                SaoriRequest::parse_arguments(case, &mut arguments).unwrap();
+                let mut arguments = vec![Some("".to_string())];
+                SaoriRequest::parse_arguments(
+                    1,
+                    case,
+                    &mut arguments,
+                    usize::MAX,
+                    &SaoriParseOptions::default(),
+                )
+                .unwrap();
                 assert_eq!(
                     arguments,
-                    vec!["".to_string(), "".to_string(), "あああ".to_string(),]
+                    vec![Some("".to_string()), None, Some("あああ".to_string())]
                 );
             }
 
@@ -465,7 +3957,13 @@ mod tests {
             fn failed_when_invalid_separator() {
                 let case = "Argument2 aaa";
                 let mut arguments = Vec::new();
-                let result = SaoriRequest::parse_arguments(case, &mut arguments);
+                let result = SaoriRequest::parse_arguments(
+                    1,
+                    case,
+                    &mut arguments,
+                    usize::MAX,
+                    &SaoriParseOptions::default(),
+                );
                 assert!(result.is_err());
             }
 
@@ -473,9 +3971,113 @@ mod tests {
             fn failed_when_invalid_no_index() {
                 let case = "Argumentaaa: aaa";
                 let mut arguments = Vec::new();
-                let result = SaoriRequest::parse_arguments(case, &mut arguments);
+                let result = SaoriRequest::parse_arguments(
+                    1,
+                    case,
+                    &mut arguments,
+                    usize::MAX,
+                    &SaoriParseOptions::default(),
+                );
+                assert!(result.is_err());
+            }
+
+            #[test]
+            fn failed_when_too_many_arguments() {
+                let case = "Argument4000000000: a";
+                let mut arguments = Vec::new();
+                assert_eq!(
+                    SaoriRequest::parse_arguments(
+                        1,
+                        case,
+                        &mut arguments,
+                        64,
+                        &SaoriParseOptions::default()
+                    ),
+                    Err(SaoriRequestError::Limit(
+                        SaoriRequestLimitError::TooManyArguments(4000000000)
+                    ))
+                );
+                assert!(arguments.is_empty());
+            }
+
+            #[test]
+            fn failed_when_huge_index_without_limits() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument999999999999: x\r\n\r\n\0";
+                assert_eq!(
+                    SaoriRequest::new(case_raw.as_bytes()),
+                    Err(SaoriRequestError::Limit(
+                        SaoriRequestLimitError::TooManyArguments(999999999999)
+                    ))
+                );
+                let options = SaoriParseOptions::new()
+                    .limits(SaoriLimits::new().max_bytes(64).max_line_length(64));
+                assert!(SaoriRequest::new_with_options(case_raw.as_bytes(), &options).is_err());
+                let result: Result<SaoriRequest, _> = case_raw.parse();
                 assert!(result.is_err());
             }
+
+            #[test]
+            fn checking_error_line() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgument0: a\r\nArgument1 b\r\n\r\n\0";
+                let result = SaoriRequest::new(case_raw.as_bytes()).unwrap_err();
+                assert_eq!(
+                    result,
+                    SaoriRequestError::Argument(SaoriRequestArgumentError::InvalidSeparator(
+                        3,
+                        "Argument1 b".to_string()
+                    ))
+                );
+                assert_eq!(result.line(), Some(3));
+            }
+        }
+
+        mod parse_extra_header {
+            use super::*;
+
+            #[test]
+            fn execute_when_unknown_header() {
+                let mut extra_headers = Vec::new();
+                SaoriRequest::parse_extra_header("X-Timeout: 10", &mut extra_headers);
+                assert_eq!(
+                    extra_headers,
+                    vec![("X-Timeout".to_string(), "10".to_string())]
+                );
+            }
+
+            #[test]
+            fn nothing_when_known_or_not_header() {
+                let mut extra_headers = Vec::new();
+                SaoriRequest::parse_extra_header("Sender: materia", &mut extra_headers);
+                SaoriRequest::parse_extra_header("Argument0: a", &mut extra_headers);
+                SaoriRequest::parse_extra_header("\0", &mut extra_headers);
+                assert!(extra_headers.is_empty());
+            }
+        }
+
+        mod extra_header {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nOption: notranslate\r\nX-Timeout: 10\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(case.extra_headers().len(), 2);
+                assert_eq!(case.extra_header("option"), Some("notranslate"));
+                assert_eq!(case.extra_header("X-Timeout"), Some("10"));
+                assert_eq!(case.extra_header("X-Other"), None);
+            }
+
+            #[test]
+            fn checking_typed_value() {
+                let case_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nX-Timeout: 10\r\nX-Retry: no\r\n\r\n\0";
+                let case = SaoriRequest::new(case_raw.as_bytes()).unwrap();
+                assert_eq!(case.header::<u32>("x-timeout"), Some(Ok(10)));
+                assert!(matches!(case.header::<u32>("X-Retry"), Some(Err(_))));
+                assert_eq!(case.header::<u32>("X-Other"), None);
+            }
         }
 
         mod parse_sender {
@@ -485,7 +4087,7 @@ mod tests {
             fn execute_when_valid_str() {
                 let case = "Sender: materia";
                 let mut sender = None;
-                SaoriRequest::parse_sender(&case, &mut sender);
+                SaoriRequest::parse_sender(case, &mut sender, Default::default());
                 assert_eq!(sender, Some("materia".to_string()));
             }
 
@@ -493,9 +4095,251 @@ mod tests {
             fn nothing_when_invalid_str() {
                 let case = "Argument3: aaaa";
                 let mut sender = None;
-                SaoriRequest::parse_sender(&case, &mut sender);
+                SaoriRequest::parse_sender(case, &mut sender, Default::default());
                 assert!(sender.is_none());
             }
         }
     }
+
+    mod saori_request_builder {
+        use super::*;
+
+        mod build {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let result = SaoriRequestBuilder::new()
+                    .charset(SaoriCharset::ShiftJIS)
+                    .command(SaoriCommand::GetVersion)
+                    .security_level(SaoriSecurityLevel::Local)
+                    .sender("materia")
+                    .argument("a")
+                    .arguments(["b", "c"])
+                    .build()
+                    .unwrap();
+                assert_eq!(
+                    result,
+                    SaoriRequest {
+                        charset: SaoriCharset::ShiftJIS,
+                        command: SaoriCommand::GetVersion,
+                        version: SaoriVersion::V1_0,
+                        security_level: Some(SaoriSecurityLevel::Local),
+                        arguments: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                        arguments_present: vec![true, true, true],
+                        sender: Some("materia".to_string()),
+                        extra_headers: vec![],
+                        raw_bytes: None,
+                        framing: SaoriFraming::default(),
+                        warnings: Vec::new(),
+                    }
+                );
+            }
+
+            #[test]
+            fn failed_when_line_break() {
+                let result = SaoriRequestBuilder::new()
+                    .sender("s\r\nArgument7: z")
+                    .security_level(SaoriSecurityLevel::Other("a\0".to_string()))
+                    .argument("a\r\nSecurityLevel: External")
+                    .extra_header("X-Extra", "1\n")
+                    .extra_header("X-Bad\r\n", "1")
+                    .extra_header("SecurityLevel", "External")
+                    .extra_header("argument1", "a")
+                    .build();
+                assert_eq!(
+                    result,
+                    Err(vec![
+                        SaoriRequestBuildError::InvalidHeader("X-Bad\r\n".to_string()),
+                        SaoriRequestBuildError::InvalidHeader("SecurityLevel".to_string()),
+                        SaoriRequestBuildError::InvalidHeader("argument1".to_string()),
+                        SaoriRequestBuildError::LineBreak("Sender".to_string()),
+                        SaoriRequestBuildError::LineBreak("SecurityLevel".to_string()),
+                        SaoriRequestBuildError::LineBreak("Argument0".to_string()),
+                        SaoriRequestBuildError::LineBreak("X-Extra".to_string()),
+                    ])
+                );
+            }
+        }
+    }
+
+    mod saori_charset {
+        use super::*;
+
+        mod encode_to {
+            use super::*;
+
+            #[test]
+            fn checking_value_with_fallback() {
+                let mut result = Vec::new();
+                SaoriCharset::ISO2022JP
+                    .encode_to("あ\u{1F600}い", &mut result, |c| {
+                        Some(format!("&#{};", c as u32))
+                    })
+                    .unwrap();
+                assert_eq!(result, b"\x1b$B$\"\x1b(B&#128512;\x1b$B$$\x1b(B".to_vec());
+            }
+
+            #[test]
+            fn failed_when_no_fallback() {
+                let mut result = Vec::new();
+                let error = SaoriCharset::ShiftJIS.encode_to("a\u{1F600}", &mut result, |_| None);
+                assert_eq!(error, Err('\u{1F600}'));
+                assert_eq!(result, b"a".to_vec());
+            }
+        }
+
+        #[test]
+        fn round_trip_all() {
+            for charset in SaoriCharset::ALL {
+                let result: SaoriCharset = charset.to_string().parse().unwrap();
+                assert_eq!(result, charset);
+            }
+        }
+
+        #[test]
+        fn success_when_alias() {
+            for (case, charset) in [
+                ("SJIS", SaoriCharset::ShiftJIS),
+                ("Windows-31J", SaoriCharset::ShiftJIS),
+                ("x-sjis", SaoriCharset::ShiftJIS),
+                ("utf-8", SaoriCharset::UTF8),
+                ("euc-jp", SaoriCharset::EucJP),
+                ("csISO2022JP", SaoriCharset::ISO2022JP),
+            ] {
+                assert_eq!(case.parse::<SaoriCharset>(), Ok(charset));
+            }
+            assert_eq!(
+                SaoriCharset::try_from("sjis").unwrap().to_str(),
+                "Shift_JIS"
+            );
+        }
+
+        #[test]
+        fn success_when_ascii_and_latin1() {
+            assert_eq!("US-ASCII".parse(), Ok(SaoriCharset::ASCII));
+            assert_eq!("latin1".parse(), Ok(SaoriCharset::Latin1));
+            assert_eq!(
+                "windows-1252".parse(),
+                Ok(SaoriCharset::Other(WINDOWS_1252))
+            );
+
+            let mut result = Vec::new();
+            assert_eq!(
+                SaoriCharset::ASCII.encode_to("caf\u{E9}", &mut result, |_| None),
+                Err('\u{E9}')
+            );
+            result.clear();
+            SaoriCharset::ASCII
+                .encode_to("caf\u{E9}", &mut result, |_| Some("?".to_string()))
+                .unwrap();
+            assert_eq!(result, b"caf?".to_vec());
+            result.clear();
+            SaoriCharset::Latin1
+                .encode_to("caf\u{E9}", &mut result, |_| None)
+                .unwrap();
+            assert_eq!(result, b"caf\xE9".to_vec());
+            assert_eq!(
+                SaoriCharset::Latin1.encode_to("\u{20AC}", &mut result, |_| None),
+                Err('\u{20AC}')
+            );
+
+            assert_eq!(SaoriCharset::ASCII.decode(b"caf\xE9"), Err(3));
+            assert_eq!(
+                SaoriCharset::Latin1.decode(b"caf\xE9").as_deref(),
+                Ok("caf\u{E9}")
+            );
+        }
+
+        #[test]
+        fn success_when_other() {
+            let result: SaoriCharset = "gbk".parse().unwrap();
+            assert_eq!(result, SaoriCharset::Other(encoding_rs::GBK));
+            assert_eq!(result.to_str(), "GBK");
+            assert_eq!(
+                SaoriCharset::try_from(encoding_rs::SHIFT_JIS),
+                Ok(SaoriCharset::ShiftJIS)
+            );
+        }
+
+        #[test]
+        fn failed_when_unknown() {
+            assert_eq!(
+                "UTF-7".parse::<SaoriCharset>(),
+                Err(SaoriRequestCharsetError::UnsupportedCharset)
+            );
+            assert_eq!(
+                "replacement".parse::<SaoriCharset>(),
+                Err(SaoriRequestCharsetError::UnsupportedCharset)
+            );
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn serde_round_trip_other() {
+            let case = SaoriCharset::Other(encoding_rs::EUC_KR);
+            let json = serde_json::to_string(&case).unwrap();
+            assert_eq!(json, r#"{"Other":"EUC-KR"}"#);
+            let result: SaoriCharset = serde_json::from_str(&json).unwrap();
+            assert_eq!(result, case);
+        }
+    }
+
+    mod saori_command {
+        use super::*;
+
+        #[test]
+        fn round_trip_all() {
+            for command in SaoriCommand::ALL {
+                let result: SaoriCommand = command.to_string().parse().unwrap();
+                assert_eq!(result, command);
+            }
+        }
+
+        #[test]
+        fn failed_when_unknown() {
+            assert_eq!(
+                "NOTIFY".parse::<SaoriCommand>(),
+                Err(SaoriRequestVersionLineError::NoCommand)
+            );
+        }
+    }
+
+    mod saori_version {
+        use super::*;
+
+        mod negotiate {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                assert_eq!(SaoriVersion::V1_0.negotiate(), Some(SaoriVersion::V1_0));
+                assert_eq!(
+                    SaoriVersion::Other("SAORI/1.1".to_string()).negotiate(),
+                    Some(SaoriVersion::V1_0)
+                );
+                assert_eq!(
+                    SaoriVersion::Other("SAORI/2.0".to_string()).negotiate(),
+                    Some(SaoriVersion::V1_0)
+                );
+            }
+
+            #[test]
+            fn none_when_lower_or_invalid() {
+                assert_eq!(
+                    SaoriVersion::Other("SAORI/0.9".to_string()).negotiate(),
+                    None
+                );
+                assert_eq!(SaoriVersion::Other("SAORI/x".to_string()).negotiate(), None);
+            }
+        }
+
+        #[test]
+        fn round_trip_all() {
+            for version in SaoriVersion::ALL {
+                let result: SaoriVersion = version.to_string().parse().unwrap();
+                assert_eq!(result, version);
+            }
+        }
+    }
 }
@@ -9,7 +9,7 @@
 //! let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
 //!
 //! let mut case = SaoriResponse::from_request(&request);
-//! case.set_result("1".to_string());
+//! case.set_result("1");
 //! case.set_values(vec!["aaa".to_string(), "bbb".to_string()]);
 //! let result = case.to_encoded_bytes().unwrap_or(SaoriResponse::error_bytes());
 //!
@@ -20,33 +20,248 @@
 //! assert_eq!(result, expect);
 //! ```
 
+use std::borrow::Cow;
 use std::fmt::Display;
+use std::ops::{Deref, DerefMut};
 
-use crate::request::{SaoriCharset, SaoriRequest, SaoriVersion};
+use crate::diagnostics::{SaoriDiagnostic, SaoriDiagnostics};
+use crate::request::{
+    SaoriCharset, SaoriFraming, SaoriLineEnding, SaoriParseOptions, SaoriRequest,
+    SaoriRequestCharsetError, SaoriRequestError, SaoriVersion, UTF8_BOM,
+};
+
+/// ベンダー拡張のヘッダの名前の接頭辞
+pub const X_SAORI_PREFIX: &str = "X-SAORI-";
 
 /// SAORIのレスポンス
-#[derive(PartialEq, Debug)]
+///
+/// 出力するヘッダの順番は、Charset、Sender、Result、Value*(番号順)、
+/// 追加のヘッダ(最初に設定した順)で固定。
+///
+/// 共通のヘッダやCharsetを持つものを複製し、`with_*`で書き換えて使うこともできる。
+///
+/// ResultとValue*は改行や`\0`を含んでいても設定できるが、別のヘッダとして読まれないよう、
+/// エンコードするときに [`SaoriResponseError::LineBreak`] になる。
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SaoriResponse {
     version: SaoriVersion,
     status: SaoriStatus,
     result: String,
     values: Vec<String>,
     charset: SaoriCharset,
+    sender: Option<String>,
+    headers: Vec<(String, String)>,
+    encode_policy: SaoriEncodePolicy,
+    empty_values: SaoriEmptyValues,
+    max_size: Option<usize>,
+    status_locked: bool,
+    content_on_error: bool,
+}
+
+/// Charsetで表現できない文字があったときの扱い
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SaoriEncodePolicy {
+    /// エラーにする
+    #[default]
+    Strict,
+    /// `?`に置き換える
+    Replace,
+    /// `&#12345;`の形の数値文字参照に置き換える
+    NumericCharacterReference,
+    /// Charsetを UTF-8 に切り替えて書き出す
+    UpgradeToUtf8,
+    /// 書き出さずに取り除く
+    Skip,
+}
+
+/// 空のValue*の扱い
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SaoriEmptyValues {
+    /// 空のValue*も書き出す。ただし、ResultもなくValue*がすべて空の場合はNo Contentになる。
+    #[default]
+    Keep,
+    /// 空のValue*も位置を保つために書き出し、すべて空でもOKにする
+    Always,
+    /// 空のValue*を書き出さない。残ったValue*の番号は変えない。
+    Skip,
+}
+
+/// Value*を書き換えるためのガード
+///
+/// [`SaoriResponse::values_mut`] から得られる。破棄されるときにstatusを切り替える(OK <=> No Content)。
+#[derive(Debug)]
+pub struct SaoriValuesMut<'a> {
+    response: &'a mut SaoriResponse,
 }
 
 /// SAORIのレスポンスのステータス
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SaoriStatus {
     OK,
     NoContent,
     BadRequest,
+    /// 403 (SecurityLevel: External を拒否したときなど)
+    Forbidden,
     InternalServerError,
+    /// 501 (知らない関数を呼ばれたときなど)
+    NotImplemented,
+    ServiceUnavailable,
+    /// 上記以外のステータス
+    ///
+    /// 番号は3桁で、理由の文字列に改行などの制御文字を含めてはいけない。
+    /// 書き出すとき(と [`SaoriResponse::try_set_status`] で設定するとき)に確かめる。
+    /// 上記の番号は、設定するときに名前のあるステータスにする。
+    Custom(u16, String),
+}
+
+/// SAORIのレスポンスのステータスの分類
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SaoriStatusClass {
+    /// 2xx
+    Success,
+    /// 4xx
+    ClientError,
+    /// 5xx
+    ServerError,
+    /// それ以外
+    Other,
 }
 
 /// SaoriResponseを処理中のエラー
 #[derive(PartialEq, Debug)]
 pub enum SaoriResponseError {
+    /// エンコードに失敗した(失敗箇所を特定できなかった)
     EncodeFailed,
+    /// Charsetで表現できない文字があった
+    Unencodable(SaoriResponseEncodeError),
+    /// 追加のヘッダの名前か値が不正だった
+    InvalidHeader(String),
+    /// ステータスの番号が3桁ではなかった
+    InvalidStatus(u16),
+    /// 書き込みに失敗した
+    WriteFailed(std::io::ErrorKind),
+    /// エンコード後の大きさ(バイト数)が上限を超えた
+    TooLarge(usize),
+    /// ステータスの理由に改行などの制御文字が含まれていた
+    InvalidStatusReason(String),
+    /// 書き出す値に改行か`\0`が含まれていた
+    LineBreak(SaoriResponseField),
+}
+
+/// SaoriResponseを読み取り中のエラー
+#[derive(PartialEq, Debug)]
+pub enum SaoriResponseParseError {
+    /// Charsetに対応していないか、デコードに失敗した
+    Charset(SaoriRequestCharsetError),
+    /// 中身がなかった
+    EmptyResponse,
+    /// 1行目(バージョンとステータス)が読み取れなかった
+    InvalidStatusLine(String),
+    /// `: `で区切られていないヘッダがあった(行番号、行)
+    InvalidHeader(usize, String),
+    /// Value*の番号が内容のバイト数以上で、大きすぎた(行番号、番号)
+    ValueIndexTooLarge(usize, usize),
+}
+
+/// SaoriResponseBuilderで組み立て中に見つかった問題
+#[derive(PartialEq, Debug)]
+pub enum SaoriResponseBuildError {
+    /// Charsetで表現できない文字があった
+    Unencodable(SaoriResponseEncodeError),
+    /// 値に改行が含まれていた(Senderと追加のヘッダでは、`\0`などの制御文字も含む)
+    LineBreak(SaoriResponseField),
+    /// エンコード後の大きさ(バイト数)が上限を超えた
+    TooLarge(usize),
+    /// statusがOKなのに、ResultもValue*もない
+    MissingResult,
+    /// 追加のヘッダの名前が不正だった
+    InvalidHeader(String),
+    /// statusの番号が3桁でないか、理由に制御文字が含まれていた
+    InvalidStatus(SaoriStatus),
+    /// 表現できない文字以外の理由で、エンコードに失敗した
+    EncodeFailed(SaoriResponseError),
+}
+
+/// SaoriResponseを組み立てる
+///
+/// `build`ですべての問題をまとめて返す。
+#[derive(PartialEq, Debug, Clone)]
+pub struct SaoriResponseBuilder {
+    version: SaoriVersion,
+    status: Option<SaoriStatus>,
+    result: String,
+    values: Vec<String>,
+    charset: SaoriCharset,
+    sender: Option<String>,
+    headers: Vec<(String, String)>,
+    encode_policy: SaoriEncodePolicy,
+    empty_values: SaoriEmptyValues,
+    max_size: Option<usize>,
+    escape_sakura_script: bool,
+    status_locked: bool,
+    content_on_error: bool,
+}
+
+/// statusと内容の組み合わせを型で保証しながら、SaoriResponseを組み立てる
+///
+/// 内容(Result、Value*)を持つとOKに、エラーのstatusを指定するとそれ以降は内容を持てなくなる。
+/// どちらもしない場合はNo Contentになる。内容以外の検査は [`SaoriResponseBuilder::build`] と同じ。
+///
+/// ```compile_fail
+/// use saori_interface_rs::*;
+///
+/// let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+/// let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+/// // Bad Requestには内容を持たせられない
+/// let response = SaoriResponse::typed_builder(&request).bad_request().result("1");
+/// ```
+#[derive(PartialEq, Debug, Clone)]
+pub struct SaoriTypedResponseBuilder<S> {
+    inner: SaoriResponseBuilder,
+    state: std::marker::PhantomData<S>,
+}
+
+/// [`SaoriTypedResponseBuilder`] の状態。まだ内容もstatusもない。
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct SaoriEmptyState;
+
+/// [`SaoriTypedResponseBuilder`] の状態。内容を持ち、OKになる。
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct SaoriContentState;
+
+/// [`SaoriTypedResponseBuilder`] の状態。エラーのstatusを持ち、内容を持てない。
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct SaoriErrorState;
+
+/// SaoriResponseのエンコードに失敗した箇所の詳細
+#[derive(PartialEq, Debug, Clone)]
+pub struct SaoriResponseEncodeError {
+    field: SaoriResponseField,
+    character: char,
+    offset: usize,
+    charset: SaoriCharset,
+}
+
+/// SaoriResponseのうち、値を持つヘッダ
+#[derive(PartialEq, Debug, Clone)]
+pub enum SaoriResponseField {
+    Result,
+    Value(usize),
+    /// 追加のヘッダ
+    Header(String),
+}
+
+/// エンコードに失敗したときの対処法の提案
+#[derive(PartialEq, Debug, Clone)]
+pub enum SaoriEncodeRemedy {
+    /// 表現できるCharsetに切り替える
+    SwitchCharset(SaoriCharset),
+    /// 表現できない文字を置換する
+    UseReplacement,
 }
 
 impl SaoriResponse {
@@ -58,64 +273,554 @@ impl SaoriResponse {
             result: String::new(),
             values: Vec::new(),
             charset: SaoriCharset::UTF8,
+            sender: None,
+            headers: Vec::new(),
+            encode_policy: SaoriEncodePolicy::Strict,
+            empty_values: SaoriEmptyValues::Keep,
+            max_size: None,
+            status_locked: false,
+            content_on_error: false,
+        }
+    }
+
+    /// リクエストのバージョンとCharsetを引き継いだ、status がBad Request である自身を生成する
+    pub fn new_bad_request_from_request(request: &SaoriRequest) -> SaoriResponse {
+        SaoriResponse {
+            status: SaoriStatus::BadRequest,
+            ..SaoriResponse::from_request(request)
         }
     }
 
     /// リクエストから自身を生成する
+    ///
+    /// バージョンは [`SaoriVersion::negotiate`] で決める。
     pub fn from_request(request: &SaoriRequest) -> SaoriResponse {
         SaoriResponse {
-            version: request.version().clone(),
+            version: request.version().negotiate().unwrap_or(SaoriVersion::V1_0),
             status: SaoriStatus::NoContent,
             result: String::new(),
             values: Vec::new(),
             charset: request.charset().clone(),
+            sender: None,
+            headers: Vec::new(),
+            encode_policy: SaoriEncodePolicy::Strict,
+            empty_values: SaoriEmptyValues::Keep,
+            max_size: None,
+            status_locked: false,
+            content_on_error: false,
+        }
+    }
+
+    /// リクエストから、ResultとValue*を持つ自身を生成する
+    ///
+    /// statusはOKになる。ただし、ResultもValue*も空の場合はNo Contentになる。
+    pub fn ok_with<I, T>(
+        request: &SaoriRequest,
+        result: impl Into<String>,
+        values: I,
+    ) -> SaoriResponse
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let mut response = SaoriResponse::from_request_with_values(request, values);
+        response.set_result(result);
+        response
+    }
+
+    /// リクエストから、statusがNo Contentの自身を生成する
+    pub fn no_content(request: &SaoriRequest) -> SaoriResponse {
+        SaoriResponse::from_request(request)
+    }
+
+    /// リクエストから、Value*に`values`を持つ自身を生成する
+    pub fn from_request_with_values<I, T>(request: &SaoriRequest, values: I) -> SaoriResponse
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let mut response = SaoriResponse::from_request(request);
+        response.extend_values(values);
+        response
+    }
+
+    /// SAORIのレスポンスのバイト列から自身を読み取る
+    ///
+    /// [`SaoriRequest::new`] と同じく、BOMとCharsetヘッダからCharsetを決める。
+    /// Value*は番号の位置に置き、飛ばされた番号は空文字列で埋める。
+    /// 仕様にないヘッダは追加のヘッダになる。statusは送られてきたものをそのまま使う。
+    pub fn parse(bytes: &[u8]) -> Result<SaoriResponse, SaoriResponseParseError> {
+        let (contents, charset) =
+            SaoriRequest::read_contents_and_charset(bytes, &SaoriParseOptions::default())
+                .map_err(SaoriResponseParseError::Charset)?;
+        let contents = contents.strip_suffix('\0').unwrap_or(&contents);
+        // 埋める数は内容のバイト数までにする
+        let max_values = contents.len();
+        let mut lines = contents
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line));
+
+        let status_line = lines
+            .next()
+            .filter(|v| !v.is_empty())
+            .ok_or(SaoriResponseParseError::EmptyResponse)?;
+        let (version, status) = SaoriResponse::parse_status_line(status_line)
+            .ok_or_else(|| SaoriResponseParseError::InvalidStatusLine(status_line.to_string()))?;
+
+        let mut response = SaoriResponse {
+            version,
+            status,
+            ..SaoriResponse::new_bad_request()
+        };
+        response.charset = charset;
+        for (index, line) in lines.enumerate() {
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_once(": ").ok_or_else(|| {
+                SaoriResponseParseError::InvalidHeader(index + 1, line.to_string())
+            })?;
+            let value_index = name
+                .strip_prefix("Value")
+                .and_then(|v| v.parse::<usize>().ok());
+            match (name, value_index) {
+                ("Charset", _) => {}
+                ("Sender", _) => response.sender = Some(value.to_string()),
+                ("Result", _) => response.result = value.to_string(),
+                (_, Some(value_index)) => {
+                    if value_index >= max_values {
+                        return Err(SaoriResponseParseError::ValueIndexTooLarge(
+                            index + 1,
+                            value_index,
+                        ));
+                    }
+                    if response.values.len() <= value_index {
+                        response.values.resize(value_index + 1, String::new());
+                    }
+                    response.values[value_index] = value.to_string();
+                }
+                _ => insert_header(&mut response.headers, name.to_string(), value.to_string()),
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// `SAORI/1.0 200 OK`のような行から、バージョンとステータスを読み取る
+    fn parse_status_line(line: &str) -> Option<(SaoriVersion, SaoriStatus)> {
+        let (version, rest) = line.split_once(' ')?;
+        let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+        let version = match version.parse() {
+            Ok(v) => v,
+            Err(_) if version.starts_with("SAORI/") => SaoriVersion::Other(version.to_string()),
+            Err(_) => return None,
+        };
+        let status = SaoriStatus::Custom(code.parse::<u16>().ok()?, reason.to_string())
+            .validated()
+            .ok()?;
+        Some((version, status))
+    }
+
+    /// GET Version への、Resultにバージョンを持つ自身を生成する
+    ///
+    /// バージョンとCharsetはリクエストから引き継ぐ。
+    pub fn version_response(request: &SaoriRequest, version: impl Into<String>) -> SaoriResponse {
+        let mut response = SaoriResponse::from_request(request);
+        response.set_result(version);
+        response
+    }
+
+    /// 数値をResultに持つ、statusがOKの自身を生成する
+    ///
+    /// バージョンとCharsetはリクエストから引き継ぐ。
+    pub fn ok_with_number(request: &SaoriRequest, number: impl Display) -> SaoriResponse {
+        let mut response = SaoriResponse::from_request(request);
+        response.set_result_display(number);
+        response
+    }
+
+    /// リクエストのバージョンとCharsetを引き継いだSaoriResponseBuilderを返す
+    ///
+    /// [`SaoriResponseBuilder::from_request`] と同じ。
+    pub fn builder(request: &SaoriRequest) -> SaoriResponseBuilder {
+        SaoriResponseBuilder::from_request(request)
+    }
+
+    /// リクエストのバージョンとCharsetを引き継いだ [`SaoriTypedResponseBuilder`] を返す
+    pub fn typed_builder(request: &SaoriRequest) -> SaoriTypedResponseBuilder<SaoriEmptyState> {
+        SaoriTypedResponseBuilder::from_request(request)
+    }
+
+    /// リクエストのエラーから自身を生成する
+    ///
+    /// エンコードの失敗(こちら側の問題)はInternal Server Error、それ以外は
+    /// (対応していないCharsetや読めないバイト列も含めて)送られてきたものの問題としてBad Request になる。
+    /// エラーの詳細は [`SaoriDiagnostic::from`] で得られる。
+    pub fn from_request_error(error: &SaoriRequestError) -> SaoriResponse {
+        let mut response = SaoriResponse::new_bad_request();
+        if let SaoriRequestError::Charset(SaoriRequestCharsetError::EncodeFailed) = error {
+            response.status = SaoriStatus::InternalServerError;
+        }
+        response
+    }
+
+    /// リクエストのエラーから、X-Error-Reason ヘッダにエラーの説明を持つ自身を生成する
+    ///
+    /// 説明は`[番号] 内容`の形で、 [`SaoriDiagnostic::from`] から得られるものと同じ。
+    pub fn from_request_error_with_reason(error: &SaoriRequestError) -> SaoriResponse {
+        let mut response = SaoriResponse::from_request_error(error);
+        let diagnostic = SaoriDiagnostic::from(error);
+        response.set_error_reason(&format!("[{}] {}", diagnostic.code(), diagnostic.message()));
+        response
+    }
+
+    pub fn version(&self) -> &SaoriVersion {
+        &self.version
+    }
+
+    pub fn charset(&self) -> &SaoriCharset {
+        &self.charset
+    }
+
+    /// Charsetを指定する
+    ///
+    /// 既定ではリクエストのCharsetを引き継ぐ。相手が対応していると分かっている場合に、
+    /// 別のCharset(常に UTF-8 など)で返すために使う。
+    pub fn set_charset(&mut self, charset: SaoriCharset) {
+        self.charset = charset;
+    }
+
+    /// 返答したモジュールの名前
+    pub fn sender(&self) -> Option<&str> {
+        self.sender.as_deref()
+    }
+
+    /// 返答したモジュールの名前を設定する。`None`で出力しなくなる。
+    ///
+    /// 改行や`\0`などの制御文字(タブを除く)を含む場合は [`SaoriResponseError::InvalidHeader`] を返す。
+    pub fn set_sender(&mut self, sender: Option<String>) -> Result<(), SaoriResponseError> {
+        if sender.as_deref().is_some_and(is_invalid_header_value) {
+            return Err(SaoriResponseError::InvalidHeader("Sender".to_string()));
         }
+        self.sender = sender;
+        Ok(())
     }
 
     pub fn status(&self) -> &SaoriStatus {
         &self.status
     }
 
+    /// statusを設定する
+    ///
+    /// 名前のあるステータスの番号の`Custom`は、そのステータスとして設定する。
+    /// 書き出せない`Custom`もそのまま設定し、エンコードするときにエラーになる。
     pub fn set_status(&mut self, status: SaoriStatus) {
-        self.status = status;
+        self.status = status.normalized();
+    }
+
+    /// statusを確かめてから設定する
+    ///
+    /// `Custom`の番号が3桁でない場合は [`SaoriResponseError::InvalidStatus`] を、
+    /// 理由に制御文字がある場合は [`SaoriResponseError::InvalidStatusReason`] を返し、statusは変えない。
+    pub fn try_set_status(&mut self, status: SaoriStatus) -> Result<(), SaoriResponseError> {
+        self.status = status.validated()?;
+        Ok(())
+    }
+
+    pub fn is_content_on_error(&self) -> bool {
+        self.content_on_error
+    }
+
+    /// エラーのstatus(4xx、5xx)でもResultとValue*を書き出すかどうかを指定する
+    ///
+    /// 既定では書き出さない。Resultをエラーの説明として表示するホストに返すときに使う。
+    pub fn set_content_on_error(&mut self, content_on_error: bool) {
+        self.content_on_error = content_on_error;
+    }
+
+    /// ResultとValue*を書き出すかどうか
+    fn writes_content(&self) -> bool {
+        self.status == SaoriStatus::OK || (self.content_on_error && self.status.is_error())
+    }
+
+    pub fn is_status_locked(&self) -> bool {
+        self.status_locked
+    }
+
+    /// statusを内容に合わせて切り替える(OK <=> No Content)のを止めるかどうかを指定する
+    ///
+    /// 空のValue*のままOKを返したい場合などに使う。止めている間は [`SaoriResponse::set_status`] で指定したstatusのまま。
+    /// 止めるのをやめたときに、statusを内容に合わせ直す。
+    pub fn set_status_locked(&mut self, status_locked: bool) {
+        self.status_locked = status_locked;
+
+        self.on_change_result_and_value();
     }
 
     pub fn result(&self) -> &str {
         &self.result
     }
 
-    pub fn set_result(&mut self, result: String) {
-        self.result = result;
+    pub fn set_result(&mut self, result: impl Into<String>) {
+        self.result = result.into();
 
         self.on_change_result_and_value();
     }
 
+    /// `result`を文字列にしてResultに適用する
+    pub fn set_result_display(&mut self, result: impl Display) {
+        self.set_result(result.to_string());
+    }
+
     pub fn values(&self) -> &[String] {
         &self.values
     }
 
+    /// 自身を消費してValue*を返す
+    pub fn into_values(self) -> Vec<String> {
+        self.values
+    }
+
     /// `index`にあるValue*に値を適用する。
-    pub fn set_value_at(&mut self, index: usize, value: String) {
+    pub fn set_value_at(&mut self, index: usize, value: impl Into<String>) {
         while self.values.len() <= index {
             self.values.push(String::new());
         }
-        self.values[index] = value;
+        self.values[index] = value.into();
         self.on_change_result_and_value();
     }
 
+    /// `value`を文字列にして`index`にあるValue*に適用する。
+    pub fn set_value_display_at(&mut self, index: usize, value: impl Display) {
+        self.set_value_at(index, value.to_string());
+    }
+
     pub fn set_values(&mut self, values: Vec<String>) {
         self.values = values;
 
         self.on_change_result_and_value();
     }
 
+    /// `values`の各要素を文字列にしてValue*に適用する
+    ///
+    /// `Vec<String>`に集めなくても、数値などをそのまま渡せる。
+    pub fn set_values_from_iter<I, T>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: Display,
+    {
+        self.values.clear();
+        self.values
+            .extend(values.into_iter().map(|value| value.to_string()));
+
+        self.on_change_result_and_value();
+    }
+
+    /// `values`を`separator`でつなげて、Value0のみに適用する
+    ///
+    /// 区切りにはバイト値1(`\x01`)がよく使われる。読み取るときは [`SaoriResponse::value_split`] を使う。
+    pub fn set_values_joined<I, T>(&mut self, values: I, separator: &str)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut joined = String::new();
+        for (index, value) in values.into_iter().enumerate() {
+            if index > 0 {
+                joined.push_str(separator);
+            }
+            joined.push_str(value.as_ref());
+        }
+        self.set_values(vec![joined]);
+    }
+
+    /// `index`にあるValue*を`separator`で分けて返す
+    ///
+    /// Value*がないか空の場合は、空のVecを返す。
+    pub fn value_split(&self, index: usize, separator: &str) -> Vec<&str> {
+        match self.values.get(index) {
+            Some(value) if !value.is_empty() => value.split(separator).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// `index`にあるValue*を、バイト値1(`\x01`)区切りのリストとして返す
+    pub fn value_byte1_list(&self, index: usize) -> Vec<&str> {
+        self.value_split(index, "\x01")
+    }
+
+    pub fn encode_policy(&self) -> SaoriEncodePolicy {
+        self.encode_policy
+    }
+
+    /// Charsetで表現できない文字があったときの扱いを指定する
+    ///
+    /// [`SaoriEncodePolicy::Strict`] 以外では、エンコードは失敗しない。
+    /// [`SaoriResponse::diagnostics`] は、置き換えられる文字も報告する。
+    pub fn set_encode_policy(&mut self, policy: SaoriEncodePolicy) {
+        self.encode_policy = policy;
+    }
+
+    pub fn empty_values(&self) -> SaoriEmptyValues {
+        self.empty_values
+    }
+
+    /// 空のValue*の扱いを指定する
+    pub fn set_empty_values(&mut self, empty_values: SaoriEmptyValues) {
+        self.empty_values = empty_values;
+
+        self.on_change_result_and_value();
+    }
+
+    pub fn max_size(&self) -> Option<usize> {
+        self.max_size
+    }
+
+    /// エンコード後の大きさ(バイト数)の上限を指定する。`None`で上限をなくす。
+    ///
+    /// 上限を超える場合、バイト列を返す関数は [`SaoriResponseError::TooLarge`] を返す。
+    /// 固定の大きさの領域に書き写すホストに返すときに使う。
+    pub fn set_max_size(&mut self, max_size: Option<usize>) {
+        self.max_size = max_size;
+    }
+
+    /// エンコード後の大きさが上限を超えていないか確かめる
+    fn check_size(&self, size: usize) -> Result<(), SaoriResponseError> {
+        check_size(self.max_size, size)
+    }
+
+    /// Value*を書き換えるためのガードを返す
+    ///
+    /// ガードが破棄されたときに、statusを内容に合わせて切り替える。
+    pub fn values_mut(&mut self) -> SaoriValuesMut<'_> {
+        SaoriValuesMut { response: self }
+    }
+
+    /// Value*をすべて取り除く
+    pub fn clear_values(&mut self) {
+        self.values.clear();
+
+        self.on_change_result_and_value();
+    }
+
+    /// Value*の末尾に値を追加する
+    pub fn push_value(&mut self, value: impl Into<String>) {
+        self.values.push(value.into());
+
+        self.on_change_result_and_value();
+    }
+
+    /// Resultを適用した自身を返す
+    pub fn with_result(mut self, result: impl Into<String>) -> SaoriResponse {
+        self.set_result(result);
+        self
+    }
+
+    /// Value*の末尾に値を追加した自身を返す
+    pub fn with_value(mut self, value: impl Into<String>) -> SaoriResponse {
+        self.push_value(value);
+        self
+    }
+
+    /// statusを適用した自身を返す
+    pub fn with_status(mut self, status: SaoriStatus) -> SaoriResponse {
+        self.set_status(status);
+        self
+    }
+
+    /// `value`を文字列にしてValue*の末尾に追加する
+    pub fn push_value_display(&mut self, value: impl Display) {
+        self.push_value(value.to_string());
+    }
+
+    /// Value*の末尾に値をまとめて追加する
+    pub fn extend_values<I, T>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.values.extend(values.into_iter().map(Into::into));
+
+        self.on_change_result_and_value();
+    }
+
+    /// 追加のヘッダ(名前と値)を、追加した順に返す
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// 名前が`name`の追加のヘッダの値を返す(大文字小文字は区別しない)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 追加のヘッダを設定する
+    ///
+    /// 同じ名前(大文字小文字は区別しない)のヘッダがある場合は、その位置のまま値を置き換える。
+    /// 名前が空・`:`や空白を含む・Charset、Sender、Result、Value*と重なる場合や、
+    /// 値が改行や`\0`などの制御文字(タブを除く)を含む場合は [`SaoriResponseError::InvalidHeader`] を返す。
+    pub fn set_header(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<(), SaoriResponseError> {
+        let name = name.into();
+        let value = value.into();
+        if !is_valid_header_name(&name) || is_invalid_header_value(&value) {
+            return Err(SaoriResponseError::InvalidHeader(name));
+        }
+
+        insert_header(&mut self.headers, name, value);
+        Ok(())
+    }
+
+    /// 名前が`name`の追加のヘッダを取り除いて、その値を返す
+    ///
+    /// 残ったヘッダの順番は変わらない。
+    pub fn remove_header(&mut self, name: &str) -> Option<String> {
+        let index = self
+            .headers
+            .iter()
+            .position(|(key, _)| key.eq_ignore_ascii_case(name))?;
+        Some(self.headers.remove(index).1)
+    }
+
+    /// `X-SAORI-{name}`の追加のヘッダの値を返す
+    pub fn x_saori_header(&self, name: &str) -> Option<&str> {
+        self.header(&SaoriResponse::x_saori_header_name(name))
+    }
+
+    /// `X-SAORI-{name}`の追加のヘッダを設定する
+    pub fn set_x_saori_header(
+        &mut self,
+        name: &str,
+        value: impl Into<String>,
+    ) -> Result<(), SaoriResponseError> {
+        self.set_header(SaoriResponse::x_saori_header_name(name), value)
+    }
+
+    /// `name`に [`X_SAORI_PREFIX`] を付けた名前を返す。すでに付いている場合はそのまま返す。
+    pub fn x_saori_header_name(name: &str) -> String {
+        match name.get(..X_SAORI_PREFIX.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(X_SAORI_PREFIX) => name.to_string(),
+            _ => format!("{}{}", X_SAORI_PREFIX, name),
+        }
+    }
+
     /// resultとvalueが変更されたときに呼ばれる
     /// statusの切替を行う(Ok <=> No Content)
     fn on_change_result_and_value(&mut self) {
+        if self.status_locked {
+            return;
+        }
+
         match self.status {
-            SaoriStatus::BadRequest | SaoriStatus::InternalServerError => {}
-            _ => {
-                let actually_empty_values = self.values.iter().all(|v| v.is_empty());
+            SaoriStatus::OK | SaoriStatus::NoContent => {
+                let actually_empty_values = self.empty_values != SaoriEmptyValues::Always
+                    && self.values.iter().all(|v| v.is_empty());
                 self.status = if self.result.is_empty()
                     && (self.values.is_empty() || actually_empty_values)
                 {
@@ -124,327 +829,3075 @@ impl SaoriResponse {
                     SaoriStatus::OK
                 };
             }
+            _ => {}
         }
     }
 
-    /// 自身をエンコードされた文字バイト列にして返す
-    pub fn to_encoded_bytes(&self) -> Result<Vec<i8>, SaoriResponseError> {
-        let response = self.to_string();
-
-        match self
-            .charset
-            .to_encoding()
-            .encode(&response, encoding::EncoderTrap::Strict)
-        {
-            Ok(v) => Ok(v.iter().map(|v| *v as i8).collect()),
-            Err(_) => Err(SaoriResponseError::EncodeFailed),
-        }
+    /// 書き出すときに使うCharsetを返す
+    ///
+    /// [`SaoriEncodePolicy::UpgradeToUtf8`] で表現できない文字がある場合のみ UTF-8 になる。
+    fn output_charset(&self) -> SaoriCharset {
+        self.output_charset_with(self.encode_policy)
     }
 
-    /// エラー時の返答バイト列を返す
-    pub fn error_bytes() -> Vec<i8> {
-        const ERROR_RESPONCE: &str =
-            "SAORI/1.0 500 Internal Server Error\r\nCharset: UTF-8\r\n\r\n\0";
-        ERROR_RESPONCE.as_bytes().iter().map(|v| *v as i8).collect()
+    /// `policy`で書き出すときに使うCharsetを返す
+    fn output_charset_with(&self, policy: SaoriEncodePolicy) -> SaoriCharset {
+        if policy == SaoriEncodePolicy::UpgradeToUtf8 && self.find_unencodable().is_some() {
+            SaoriCharset::UTF8
+        } else {
+            self.charset.clone()
+        }
     }
-}
 
-impl Display for SaoriResponse {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut result = format!(
-            "{} {} {}\r\nCharset: {}\r\n",
+    /// 書き出す内容を、1行(CRLFを含む)ずつ`f`に渡す
+    ///
+    /// 最後は空行と`\0`になる。`f`がエラーを返した場合はそこで止める。
+    fn try_for_each_line<E>(
+        &self,
+        charset: &SaoriCharset,
+        mut f: impl FnMut(&str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut line = format!(
+            "{} {} {}\r\n",
             self.version.to_str(),
             self.status.to_code(),
-            self.status.to_str(),
-            self.charset.to_str()
+            self.status.to_str()
         );
-        if self.status == SaoriStatus::OK {
-            if !self.result.is_empty() {
-                // Result: {}\r\n
-                result.push_str("Result: ");
-                result.push_str(&self.result);
-                result.push_str("\r\n");
-            }
-            for (index, value) in self.values.iter().enumerate() {
-                // Value{}: {}\r\n
-                result.push_str("Value");
-                result.push_str(&index.to_string());
-                result.push_str(": ");
-                result.push_str(value);
-                result.push_str("\r\n");
-            }
+        f(&line)?;
+        for (name, value) in self.serialized_headers_with(charset) {
+            write_header_line(&mut line, &name, &value);
+            f(&line)?;
         }
-        write!(f, "{}\r\n\0", result)
+        f("\r\n\0")
     }
-}
 
-impl SaoriStatus {
-    pub fn to_code(&self) -> u16 {
-        match self {
-            SaoriStatus::OK => 200,
-            SaoriStatus::NoContent => 204,
-            SaoriStatus::BadRequest => 400,
-            SaoriStatus::InternalServerError => 500,
-        }
+    /// 書き出せない内容がないかを確かめてから、[`SaoriResponse::try_for_each_line`] と同じく1行ずつ`f`に渡す
+    ///
+    /// エンコードするときは、すべてこれを通す。
+    fn try_for_each_checked_line(
+        &self,
+        charset: &SaoriCharset,
+        f: impl FnMut(&str) -> Result<(), SaoriResponseError>,
+    ) -> Result<(), SaoriResponseError> {
+        self.status.check()?;
+        self.check_line_breaks()?;
+        self.try_for_each_line(charset, f)
     }
 
-    pub fn to_str(&self) -> &'static str {
-        match self {
-            SaoriStatus::OK => "OK",
-            SaoriStatus::NoContent => "No Content",
-            SaoriStatus::BadRequest => "Bad Request",
-            SaoriStatus::InternalServerError => "Internal Server Error",
+    /// 書き出す値に改行か`\0`がないかを確かめる
+    fn check_line_breaks(&self) -> Result<(), SaoriResponseError> {
+        if self.writes_content() {
+            if has_line_break(&self.result) {
+                return Err(SaoriResponseError::LineBreak(SaoriResponseField::Result));
+            }
+            if let Some(index) = self.values.iter().position(|v| has_line_break(v)) {
+                return Err(SaoriResponseError::LineBreak(SaoriResponseField::Value(
+                    index,
+                )));
+            }
+        }
+        if self.sender.as_deref().is_some_and(has_line_break) {
+            return Err(SaoriResponseError::LineBreak(SaoriResponseField::Header(
+                "Sender".to_string(),
+            )));
+        }
+        match self.headers.iter().find(|(_, value)| has_line_break(value)) {
+            Some((name, _)) => Err(SaoriResponseError::LineBreak(SaoriResponseField::Header(
+                name.clone(),
+            ))),
+            None => Ok(()),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// `Display`で書き出される長さ(UTF-8でのバイト数)を返す
+    ///
+    /// 出力を確保するときの大きさの見積もりに使う。
+    pub fn serialized_len(&self) -> usize {
+        self.serialized_len_with(&self.output_charset())
+    }
 
-    mod saori_response {
-        use super::*;
+    fn serialized_len_with(&self, charset: &SaoriCharset) -> usize {
+        let status_line = self.version.to_str().len()
+            + self.status.to_code().to_string().len()
+            + self.status.to_str().len()
+            + 4;
+        let headers: usize = self
+            .serialized_headers_with(charset)
+            .iter()
+            .map(|(name, value)| name.len() + value.len() + 4)
+            .sum();
+        status_line + headers + 3
+    }
 
-        mod new_bad_request {
-            use super::*;
+    /// 書き出されるヘッダを、書き出される順に(名前, 値)で返す
+    ///
+    /// 順番は、Charset、Sender、Result、Value*(番号順)、追加のヘッダ(最初に設定した順)。
+    /// ResultとValue*はstatusがOKのとき(と [`SaoriResponse::set_content_on_error`] でエラーのとき)のみ含まれる。
+    /// `Display`の出力と常に一致する。
+    pub fn serialized_headers(&self) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        self.serialized_headers_with(&self.output_charset())
+    }
 
-            #[test]
-            fn checking_value() {
-                let case = SaoriResponse::new_bad_request();
-                assert_eq!(
-                    case,
-                    SaoriResponse {
-                        version: SaoriVersion::V1_0,
-                        status: SaoriStatus::BadRequest,
-                        result: String::new(),
-                        values: vec![],
-                        charset: SaoriCharset::UTF8
-                    }
-                );
+    fn serialized_headers_with(&self, charset: &SaoriCharset) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+        let mut headers = vec![(Cow::Borrowed("Charset"), Cow::Borrowed(charset.to_str()))];
+        if let Some(sender) = &self.sender {
+            headers.push((Cow::Borrowed("Sender"), Cow::Borrowed(sender.as_str())));
+        }
+        if self.writes_content() {
+            if !self.result.is_empty() {
+                headers.push((Cow::Borrowed("Result"), Cow::Borrowed(self.result.as_str())));
             }
+            let skip_empty = self.empty_values == SaoriEmptyValues::Skip;
+            let values = self
+                .values
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| !(skip_empty && value.is_empty()));
+            headers.extend(values.map(|(index, value)| {
+                (
+                    Cow::Owned(format!("Value{}", index)),
+                    Cow::Borrowed(value.as_str()),
+                )
+            }));
         }
+        headers.extend(
+            self.headers
+                .iter()
+                .map(|(name, value)| (Cow::Borrowed(name.as_str()), Cow::Borrowed(value.as_str()))),
+        );
+        headers
+    }
 
-        mod from_request {
-            use super::*;
-
-            #[test]
-            fn checking_value() {
-                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
-                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
-                let case = SaoriResponse::from_request(&request);
-                assert_eq!(
-                    case,
-                    SaoriResponse {
-                        version: SaoriVersion::V1_0,
-                        status: SaoriStatus::NoContent,
-                        result: String::new(),
-                        values: vec![],
-                        charset: SaoriCharset::ShiftJIS
-                    }
-                );
-            }
+    /// 自身をエンコードして`writer`に書き込み、書き込んだバイト数を返す
+    ///
+    /// 全体を一度に組み立てずに、1行ずつエンコードして書き込む。
+    /// エンコードに失敗した場合、それより前の行はすでに書き込まれている。
+    /// 大きさの上限がある場合は、全体をエンコードして確かめてから書き込む。
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<usize, SaoriResponseError> {
+        if self.max_size.is_some() {
+            let bytes = self.to_encoded_bytes_u8()?;
+            writer
+                .write_all(&bytes)
+                .map_err(|e| SaoriResponseError::WriteFailed(e.kind()))?;
+            return Ok(bytes.len());
         }
 
-        mod set_result {
-            use super::*;
+        let charset = self.output_charset();
+        let mut buffer = Vec::new();
+        let mut written = 0;
+        self.try_for_each_checked_line(&charset, |line| {
+            buffer.clear();
+            charset
+                .encode_to(line, &mut buffer, |c| self.encode_policy.fallback(c))
+                .map_err(|_| self.encode_error())?;
+            writer
+                .write_all(&buffer)
+                .map_err(|e| SaoriResponseError::WriteFailed(e.kind()))?;
+            written += buffer.len();
+            Ok(())
+        })?;
+        Ok(written)
+    }
 
-            #[test]
-            fn checking_value_no_empty() {
+    /// 自身を消費して、エンコードされた文字バイト列にして返す
+    ///
+    /// 全体の文字列を組み立てずに1行ずつエンコードし、その領域をそのまま返す。
+    /// 出力は [`SaoriResponse::to_encoded_bytes`] と同じ。
+    pub fn into_encoded_bytes(self) -> Result<Vec<i8>, SaoriResponseError> {
+        let mut buffer = Vec::new();
+        self.encode_into_with(&mut buffer, self.encode_policy)?;
+        Ok(SaoriResponse::into_ffi_bytes(buffer))
+    }
+
+    /// `buffer`を空にしてから自身をエンコードして書き込み、書き込んだバイト数を返す
+    ///
+    /// 同じ`buffer`を使い回すことで、レスポンスごとの確保を避けられる。
+    /// エンコードに失敗した場合、`buffer`には途中までの内容が残る。
+    pub fn to_encoded_bytes_into(&self, buffer: &mut Vec<u8>) -> Result<usize, SaoriResponseError> {
+        self.encode_into_with(buffer, self.encode_policy)
+    }
+
+    /// 設定されたものの代わりに`policy`を使って、自身をエンコードされた文字バイト列にして返す
+    ///
+    /// 自身の設定は変えないため、ログには置き換えて、ベースウェアへは厳密に書き出す、
+    /// のように呼び出しごとに使い分けられる。
+    ///
+    /// ```
+    /// use saori_interface_rs::*;
+    ///
+    /// let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+    /// let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+    /// let mut response = SaoriResponse::from_request(&request);
+    /// response.set_result("a\u{1F600}b".to_string());
+    ///
+    /// // testing
+    /// assert!(response.to_encoded_bytes().is_err());
+    /// let result = response
+    ///     .to_encoded_bytes_with_policy(SaoriEncodePolicy::Skip)
+    ///     .unwrap();
+    /// let result: Vec<u8> = result.into_iter().map(|v| v as u8).collect();
+    /// assert_eq!(
+    ///     result,
+    ///     b"SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: ab\r\n\r\n\0".to_vec()
+    /// );
+    /// ```
+    pub fn to_encoded_bytes_with_policy(
+        &self,
+        policy: SaoriEncodePolicy,
+    ) -> Result<Vec<i8>, SaoriResponseError> {
+        let mut buffer = Vec::new();
+        self.encode_into_with(&mut buffer, policy)?;
+        Ok(SaoriResponse::into_ffi_bytes(buffer))
+    }
+
+    /// `buffer`を空にしてから、`policy`で自身をエンコードして書き込む
+    fn encode_into_with(
+        &self,
+        buffer: &mut Vec<u8>,
+        policy: SaoriEncodePolicy,
+    ) -> Result<usize, SaoriResponseError> {
+        buffer.clear();
+        let charset = self.output_charset_with(policy);
+        buffer.reserve(self.serialized_len_with(&charset));
+        self.try_for_each_checked_line(&charset, |line| {
+            charset
+                .encode_to(line, buffer, |c| policy.fallback(c))
+                .map_err(|_| self.encode_error())
+        })?;
+        self.check_size(buffer.len())?;
+        Ok(buffer.len())
+    }
+
+    /// 改行やstatusを確かめずに、`policy`でエンコードしたときの大きさ(バイト数)を返す
+    fn unchecked_encoded_len(
+        &self,
+        policy: SaoriEncodePolicy,
+    ) -> Result<usize, SaoriResponseError> {
+        let charset = self.output_charset_with(policy);
+        let mut buffer = Vec::new();
+        let mut len = 0;
+        self.try_for_each_line(&charset, |line| {
+            buffer.clear();
+            charset
+                .encode_to(line, &mut buffer, |c| policy.fallback(c))
+                .map_err(|_| self.encode_error())?;
+            len += buffer.len();
+            Ok(())
+        })?;
+        Ok(len)
+    }
+
+    /// エンコードに失敗したときのエラーを返す
+    fn encode_error(&self) -> SaoriResponseError {
+        self.find_unencodable()
+            .map_or(SaoriResponseError::EncodeFailed, |e| {
+                SaoriResponseError::Unencodable(e)
+            })
+    }
+
+    /// 自身をエンコードされた文字バイト列(`u8`)にして返す
+    ///
+    /// FFI以外で使うときはこちらを使う。SHIORIに返すための`Vec<i8>`は
+    /// [`SaoriResponse::into_ffi_bytes`] で複製せずに得られる。
+    pub fn to_encoded_bytes_u8(&self) -> Result<Vec<u8>, SaoriResponseError> {
+        let mut buffer = Vec::new();
+        self.to_encoded_bytes_into(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// `Vec<u8>`を、確保済みの領域をそのまま使って`Vec<i8>`にする
+    ///
+    /// Windows の C ABI (`char*`) に渡すためのもので、要素ごとの変換や複製は行わない。
+    pub fn into_ffi_bytes(bytes: Vec<u8>) -> Vec<i8> {
+        let mut bytes = std::mem::ManuallyDrop::new(bytes);
+        let (ptr, len, capacity) = (bytes.as_mut_ptr(), bytes.len(), bytes.capacity());
+        // SAFETY: u8とi8は大きさも配置も同じで、どのビット列も有効な値である。
+        // 元のVecはManuallyDropで解放されないため、領域の所有権は新しいVecに移る。
+        unsafe { Vec::from_raw_parts(ptr as *mut i8, len, capacity) }
+    }
+
+    /// 自身をエンコードされた文字バイト列にして返す
+    pub fn to_encoded_bytes(&self) -> Result<Vec<i8>, SaoriResponseError> {
+        self.to_encoded_bytes_with_framing(&SaoriFraming::default())
+    }
+
+    /// `framing`の形式で、自身をエンコードされた文字バイト列にして返す
+    ///
+    /// 末尾の`\0`の有無と改行(CRLFかLF)を選べる。改行が`Mixed`の場合はCRLFにし、
+    /// BOMはUTF-8かUTF-16のときのみ付ける。既定値では [`SaoriResponse::to_encoded_bytes`] と同じ。
+    pub fn to_encoded_bytes_with_framing(
+        &self,
+        framing: &SaoriFraming,
+    ) -> Result<Vec<i8>, SaoriResponseError> {
+        let charset = self.output_charset();
+        let mut response = String::with_capacity(self.serialized_len_with(&charset));
+        self.try_for_each_checked_line(&charset, |line| {
+            response.push_str(line);
+            Ok(())
+        })?;
+        if !framing.has_nul() {
+            response.pop();
+        }
+        if framing.line_ending() == SaoriLineEnding::Lf {
+            response = response.replace("\r\n", "\n");
+        }
+
+        let mut result = Vec::with_capacity(response.len() + UTF8_BOM.len());
+        if let Some(bom) = charset.bom().filter(|_| framing.has_bom()) {
+            result.extend_from_slice(bom);
+        }
+        match charset.encode_to(&response, &mut result, |c| self.encode_policy.fallback(c)) {
+            Ok(_) => {
+                self.check_size(result.len())?;
+                Ok(SaoriResponse::into_ffi_bytes(result))
+            }
+            Err(_) => Err(self.encode_error()),
+        }
+    }
+
+    /// 出力されるResult、Value*と追加のヘッダから、Charsetで表現できない最初の文字を探す
+    fn find_unencodable(&self) -> Option<SaoriResponseEncodeError> {
+        self.find_all_unencodable().into_iter().next()
+    }
+
+    /// 出力されるResult、Value*と追加のヘッダから、Charsetで表現できない文字をすべて探す
+    fn find_all_unencodable(&self) -> Vec<SaoriResponseEncodeError> {
+        let mut fields = Vec::new();
+        if self.writes_content() {
+            fields.push((SaoriResponseField::Result, &self.result));
+            fields.extend(
+                self.values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| (SaoriResponseField::Value(index), value)),
+            );
+        }
+        fields.extend(
+            self.sender
+                .iter()
+                .map(|value| (SaoriResponseField::Header("Sender".to_string()), value)),
+        );
+        fields.extend(
+            self.headers
+                .iter()
+                .map(|(name, value)| (SaoriResponseField::Header(name.clone()), value)),
+        );
+
+        let mut found = Vec::new();
+        for (field, value) in fields {
+            find_unencodable_in(&field, value, &self.charset, &mut found);
+        }
+
+        found
+    }
+
+    /// 自身をエンコードしたときに起きる問題を返す
+    pub fn diagnostics(&self) -> SaoriDiagnostics {
+        self.find_all_unencodable()
+            .into_iter()
+            .map(|e| SaoriDiagnostic::from(&SaoriResponseError::Unencodable(e)))
+            .collect()
+    }
+
+    /// 人が読むための、複数行に整形した文字列を返す
+    ///
+    /// エンコードで起きる問題も併せて表示する。
+    /// ログやデバッグ表示のためのもので、SAORIのレスポンスとしては使えない。
+    pub fn to_pretty_string(&self) -> String {
+        let mut rows = vec![
+            ("Version".to_string(), self.version.to_string()),
+            ("Status".to_string(), self.status.to_string()),
+            ("Charset".to_string(), self.charset.to_string()),
+        ];
+        rows.extend(
+            self.sender
+                .iter()
+                .map(|v| ("Sender".to_string(), v.clone())),
+        );
+        rows.push(("Result".to_string(), self.result.clone()));
+        rows.extend(
+            self.values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (format!("Value{}", index), value.clone())),
+        );
+        rows.extend(self.headers.iter().cloned());
+        rows.extend(self.diagnostics().iter().map(|v| {
+            (
+                format!("{:?}", v.severity()),
+                format!("[{}] {}", v.code(), v.message()),
+            )
+        }));
+
+        crate::pretty::format_rows("SAORI response", &rows)
+    }
+
+    /// X-Error-Reason ヘッダに`reason`を入れる。改行などの制御文字は空白に置き換える。
+    fn set_error_reason(&mut self, reason: &str) {
+        let reason = reason.replace(char::is_control, " ");
+        insert_header(&mut self.headers, "X-Error-Reason".to_string(), reason);
+    }
+
+    /// エラー時の返答バイト列を返す
+    pub fn error_bytes() -> Vec<i8> {
+        const ERROR_RESPONCE: &str =
+            "SAORI/1.0 500 Internal Server Error\r\nCharset: UTF-8\r\n\r\n\0";
+        SaoriResponse::into_ffi_bytes(ERROR_RESPONCE.as_bytes().to_vec())
+    }
+
+    /// `charset`で、500 Internal Server Errorのバイト列を返す
+    ///
+    /// `reason`があれば X-Error-Reason ヘッダに入れる。改行などの制御文字は空白に置き換え、
+    /// 表現できない文字は`?`に置き換える。それでも失敗した場合は [`SaoriResponse::error_bytes`] を返す。
+    pub fn error_bytes_with(charset: &SaoriCharset, reason: Option<&str>) -> Vec<i8> {
+        let mut response = SaoriResponse::new_bad_request();
+        response.set_status(SaoriStatus::InternalServerError);
+        response.set_charset(charset.clone());
+        response.set_encode_policy(SaoriEncodePolicy::Replace);
+        if let Some(reason) = reason {
+            response.set_error_reason(reason);
+        }
+
+        response
+            .into_encoded_bytes()
+            .unwrap_or_else(|_| SaoriResponse::error_bytes())
+    }
+}
+
+/// [`SaoriResponse::from_request_error`] と同じ。
+impl From<SaoriRequestError> for SaoriResponse {
+    fn from(error: SaoriRequestError) -> Self {
+        SaoriResponse::from_request_error(&error)
+    }
+}
+
+/// [`SaoriResponse::from_request_error`] と同じ。
+impl From<&SaoriRequestError> for SaoriResponse {
+    fn from(error: &SaoriRequestError) -> Self {
+        SaoriResponse::from_request_error(error)
+    }
+}
+
+/// [`SaoriResponse::to_encoded_bytes`] と同じ。
+impl TryFrom<&SaoriResponse> for Vec<i8> {
+    type Error = SaoriResponseError;
+
+    fn try_from(response: &SaoriResponse) -> Result<Self, Self::Error> {
+        response.to_encoded_bytes()
+    }
+}
+
+/// [`SaoriResponse::to_encoded_bytes_u8`] と同じ。
+impl TryFrom<&SaoriResponse> for Vec<u8> {
+    type Error = SaoriResponseError;
+
+    fn try_from(response: &SaoriResponse) -> Result<Self, Self::Error> {
+        response.to_encoded_bytes_u8()
+    }
+}
+
+/// Value*に値を持つ自身を生成する。Charsetは UTF-8 になる。
+impl<T: Into<String>> FromIterator<T> for SaoriResponse {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut response = SaoriResponse::new_bad_request();
+        response.status = SaoriStatus::NoContent;
+        response.extend_values(iter);
+        response
+    }
+}
+
+impl<T: Into<String>> Extend<T> for SaoriResponse {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.extend_values(iter);
+    }
+}
+
+impl SaoriEncodePolicy {
+    /// 表現できない文字`c`の代わりに書き出す文字列を返す。`None`はエラーにすることを表す。
+    fn fallback(self, c: char) -> Option<String> {
+        match self {
+            SaoriEncodePolicy::Strict | SaoriEncodePolicy::UpgradeToUtf8 => None,
+            SaoriEncodePolicy::Replace => Some("?".to_string()),
+            SaoriEncodePolicy::Skip => Some(String::new()),
+            SaoriEncodePolicy::NumericCharacterReference => Some(format!("&#{};", c as u32)),
+        }
+    }
+}
+
+impl Deref for SaoriValuesMut<'_> {
+    type Target = Vec<String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.response.values
+    }
+}
+
+impl DerefMut for SaoriValuesMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.response.values
+    }
+}
+
+impl Drop for SaoriValuesMut<'_> {
+    fn drop(&mut self) {
+        self.response.on_change_result_and_value();
+    }
+}
+
+impl SaoriResponseError {
+    /// エラーの番号を返す
+    ///
+    /// 番号はクレートのバージョンをまたいで変わらない。
+    /// SaoriResponse関連は20xx。
+    pub const fn code(&self) -> u32 {
+        match self {
+            SaoriResponseError::EncodeFailed => 2001,
+            SaoriResponseError::Unencodable(_) => 2002,
+            SaoriResponseError::InvalidHeader(_) => 2003,
+            SaoriResponseError::InvalidStatus(_) => 2004,
+            SaoriResponseError::WriteFailed(_) => 2005,
+            SaoriResponseError::TooLarge(_) => 2006,
+            SaoriResponseError::InvalidStatusReason(_) => 2007,
+            SaoriResponseError::LineBreak(_) => 2008,
+        }
+    }
+}
+
+impl SaoriResponseParseError {
+    /// エラーの番号を返す
+    ///
+    /// 番号はクレートのバージョンをまたいで変わらない。
+    /// SaoriResponseの読み取り関連は22xx。
+    pub const fn code(&self) -> u32 {
+        match self {
+            SaoriResponseParseError::Charset(_) => 2201,
+            SaoriResponseParseError::EmptyResponse => 2202,
+            SaoriResponseParseError::InvalidStatusLine(_) => 2203,
+            SaoriResponseParseError::InvalidHeader(_, _) => 2204,
+            SaoriResponseParseError::ValueIndexTooLarge(_, _) => 2205,
+        }
+    }
+
+    /// エラーが起きた行(0始まり)を返す
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            SaoriResponseParseError::Charset(_) => None,
+            SaoriResponseParseError::EmptyResponse
+            | SaoriResponseParseError::InvalidStatusLine(_) => Some(0),
+            SaoriResponseParseError::InvalidHeader(line, _)
+            | SaoriResponseParseError::ValueIndexTooLarge(line, _) => Some(*line),
+        }
+    }
+}
+
+impl SaoriResponseBuildError {
+    /// エラーの番号を返す
+    ///
+    /// 番号はクレートのバージョンをまたいで変わらない。
+    /// SaoriResponseBuilder関連は21xx。
+    pub const fn code(&self) -> u32 {
+        match self {
+            SaoriResponseBuildError::Unencodable(_) => 2101,
+            SaoriResponseBuildError::LineBreak(_) => 2102,
+            SaoriResponseBuildError::TooLarge(_) => 2103,
+            SaoriResponseBuildError::MissingResult => 2104,
+            SaoriResponseBuildError::InvalidHeader(_) => 2105,
+            SaoriResponseBuildError::InvalidStatus(_) => 2106,
+            SaoriResponseBuildError::EncodeFailed(_) => 2107,
+        }
+    }
+}
+
+impl SaoriResponseEncodeError {
+    /// 表現できない文字があったヘッダ
+    pub fn field(&self) -> &SaoriResponseField {
+        &self.field
+    }
+
+    /// 表現できなかった文字
+    pub fn character(&self) -> char {
+        self.character
+    }
+
+    /// ヘッダの値の中での、文字のバイト位置(UTF-8)
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// エンコードしようとしたCharset
+    pub fn charset(&self) -> &SaoriCharset {
+        &self.charset
+    }
+
+    /// 対処法の提案を返す
+    pub fn remedy(&self) -> SaoriEncodeRemedy {
+        if self.charset != SaoriCharset::UTF8 {
+            SaoriEncodeRemedy::SwitchCharset(SaoriCharset::UTF8)
+        } else {
+            SaoriEncodeRemedy::UseReplacement
+        }
+    }
+}
+
+/// 追加のヘッダの名前として使えるかどうか
+fn is_valid_header_name(name: &str) -> bool {
+    let is_value = name
+        .get(..5)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("Value"))
+        && name[5..].parse::<usize>().is_ok();
+    let reserved = ["Charset", "Sender", "Result"]
+        .iter()
+        .any(|v| name.eq_ignore_ascii_case(v))
+        || is_value;
+    !name.is_empty() && !reserved && name.chars().all(|c| c.is_ascii_graphic() && c != ':')
+}
+
+/// 改行か`\0`を含むかどうか
+fn has_line_break(value: &str) -> bool {
+    value.contains(['\r', '\n', '\0'])
+}
+
+/// ヘッダの値として書き出せないかどうか
+///
+/// 改行は別のヘッダに、`\0`はホストの読み取りの終端になるため、タブ以外の制御文字を許さない。
+fn is_invalid_header_value(value: &str) -> bool {
+    value.chars().any(|c| c.is_ascii_control() && c != '\t')
+}
+
+/// 追加のヘッダを設定する。同じ名前のものがある場合は、その位置のまま値を置き換える。
+fn insert_header(headers: &mut Vec<(String, String)>, name: String, value: String) {
+    match headers
+        .iter_mut()
+        .find(|(key, _)| key.eq_ignore_ascii_case(&name))
+    {
+        Some((_, old)) => *old = value,
+        None => headers.push((name, value)),
+    }
+}
+
+/// `value`から、`charset`で表現できない文字をすべて探して`found`に加える
+fn find_unencodable_in(
+    field: &SaoriResponseField,
+    value: &str,
+    charset: &SaoriCharset,
+    found: &mut Vec<SaoriResponseEncodeError>,
+) {
+    let mut buffer = Vec::new();
+    for (offset, character) in value.char_indices() {
+        buffer.clear();
+        if charset
+            .encode_to(character.encode_utf8(&mut [0; 4]), &mut buffer, |_| None)
+            .is_err()
+        {
+            found.push(SaoriResponseEncodeError {
+                field: field.clone(),
+                character,
+                offset,
+                charset: charset.clone(),
+            });
+        }
+    }
+}
+
+/// さくらスクリプトに埋め込めるように、`\`と`%`をエスケープする
+///
+/// `\`は`\\`に、`%`は`\%`になる。どちらも含まない場合は複製しない。
+///
+/// ```
+/// use saori_interface_rs::*;
+///
+/// assert_eq!(escape_sakura_script("C:\\a 100%"), "C:\\\\a 100\\%");
+/// ```
+pub fn escape_sakura_script(value: &str) -> Cow<'_, str> {
+    if !value.contains(['\\', '%']) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut escaped = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        if c == '\\' || c == '%' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    Cow::Owned(escaped)
+}
+
+/// `size`が`max_size`を超えていればエラーを返す
+fn check_size(max_size: Option<usize>, size: usize) -> Result<(), SaoriResponseError> {
+    match max_size {
+        Some(max_size) if size > max_size => Err(SaoriResponseError::TooLarge(size)),
+        _ => Ok(()),
+    }
+}
+
+/// `{name}: {value}\r\n`を`line`に書く
+fn write_header_line(line: &mut String, name: &str, value: &str) {
+    line.clear();
+    line.push_str(name);
+    line.push_str(": ");
+    line.push_str(value);
+    line.push_str("\r\n");
+}
+
+impl Display for SaoriResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.try_for_each_line(&self.output_charset(), |line| f.write_str(line))
+    }
+}
+
+impl SaoriStatus {
+    /// Custom以外のすべてのステータス
+    pub const ALL: [SaoriStatus; 7] = [
+        SaoriStatus::OK,
+        SaoriStatus::NoContent,
+        SaoriStatus::BadRequest,
+        SaoriStatus::Forbidden,
+        SaoriStatus::InternalServerError,
+        SaoriStatus::NotImplemented,
+        SaoriStatus::ServiceUnavailable,
+    ];
+
+    /// 番号からステータスを返す
+    ///
+    /// 知らない番号は、理由が空の`Custom`になる。
+    pub fn from_code(code: u16) -> SaoriStatus {
+        SaoriStatus::ALL
+            .into_iter()
+            .find(|v| v.to_code() == code)
+            .unwrap_or(SaoriStatus::Custom(code, String::new()))
+    }
+
+    pub fn to_code(&self) -> u16 {
+        match self {
+            SaoriStatus::OK => 200,
+            SaoriStatus::NoContent => 204,
+            SaoriStatus::BadRequest => 400,
+            SaoriStatus::Forbidden => 403,
+            SaoriStatus::InternalServerError => 500,
+            SaoriStatus::NotImplemented => 501,
+            SaoriStatus::ServiceUnavailable => 503,
+            SaoriStatus::Custom(code, _) => *code,
+        }
+    }
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            SaoriStatus::OK => "OK",
+            SaoriStatus::NoContent => "No Content",
+            SaoriStatus::BadRequest => "Bad Request",
+            SaoriStatus::Forbidden => "Forbidden",
+            SaoriStatus::InternalServerError => "Internal Server Error",
+            SaoriStatus::NotImplemented => "Not Implemented",
+            SaoriStatus::ServiceUnavailable => "Service Unavailable",
+            SaoriStatus::Custom(_, reason) => reason,
+        }
+    }
+
+    /// ステータスの分類を返す
+    pub fn class(&self) -> SaoriStatusClass {
+        match self.to_code() {
+            200..=299 => SaoriStatusClass::Success,
+            400..=499 => SaoriStatusClass::ClientError,
+            500..=599 => SaoriStatusClass::ServerError,
+            _ => SaoriStatusClass::Other,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.class() == SaoriStatusClass::Success
+    }
+
+    pub fn is_client_error(&self) -> bool {
+        self.class() == SaoriStatusClass::ClientError
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        self.class() == SaoriStatusClass::ServerError
+    }
+
+    /// 4xxか5xxかどうか
+    pub fn is_error(&self) -> bool {
+        self.is_client_error() || self.is_server_error()
+    }
+
+    /// 書き出せるステータスかを確かめる
+    fn check(&self) -> Result<(), SaoriResponseError> {
+        match self {
+            SaoriStatus::Custom(_, reason) if reason.contains(char::is_control) => {
+                Err(SaoriResponseError::InvalidStatusReason(reason.clone()))
+            }
+            SaoriStatus::Custom(code, _) => SaoriStatus::try_from(*code).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    /// 名前のあるステータスの番号の`Custom`は、そのステータスにして返す
+    fn normalized(self) -> SaoriStatus {
+        match self {
+            SaoriStatus::Custom(code, reason) => match SaoriStatus::try_from(code) {
+                Ok(SaoriStatus::Custom(_, _)) | Err(_) => SaoriStatus::Custom(code, reason),
+                Ok(status) => status,
+            },
+            status => status,
+        }
+    }
+
+    /// 書き出せるステータスかを確かめ、[`SaoriStatus::normalized`] にして返す
+    fn validated(self) -> Result<SaoriStatus, SaoriResponseError> {
+        self.check()?;
+        Ok(self.normalized())
+    }
+}
+
+/// 3桁の番号は [`SaoriStatus::from_code`] と同じ。それ以外は [`SaoriResponseError::InvalidStatus`] を返す。
+impl TryFrom<u16> for SaoriStatus {
+    type Error = SaoriResponseError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        if (100..=999).contains(&value) {
+            Ok(SaoriStatus::from_code(value))
+        } else {
+            Err(SaoriResponseError::InvalidStatus(value))
+        }
+    }
+}
+
+impl Display for SaoriStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.to_code(), self.to_str())
+    }
+}
+
+impl Default for SaoriResponseBuilder {
+    fn default() -> Self {
+        SaoriResponseBuilder::new()
+    }
+}
+
+impl SaoriResponseBuilder {
+    /// Charsetが UTF-8 の自身を生成する
+    pub fn new() -> SaoriResponseBuilder {
+        SaoriResponseBuilder {
+            version: SaoriVersion::V1_0,
+            status: None,
+            result: String::new(),
+            values: Vec::new(),
+            charset: SaoriCharset::UTF8,
+            sender: None,
+            headers: Vec::new(),
+            encode_policy: SaoriEncodePolicy::Strict,
+            empty_values: SaoriEmptyValues::Keep,
+            max_size: None,
+            escape_sakura_script: false,
+            status_locked: false,
+            content_on_error: false,
+        }
+    }
+
+    /// リクエストのバージョンとCharsetを引き継いだ自身を生成する
+    pub fn from_request(request: &SaoriRequest) -> SaoriResponseBuilder {
+        SaoriResponseBuilder {
+            version: request.version().negotiate().unwrap_or(SaoriVersion::V1_0),
+            charset: request.charset().clone(),
+            ..SaoriResponseBuilder::new()
+        }
+    }
+
+    /// statusを指定する。指定しない場合は、内容に合わせてOKかNo Contentになる。
+    pub fn status(mut self, status: SaoriStatus) -> SaoriResponseBuilder {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn charset(mut self, charset: SaoriCharset) -> SaoriResponseBuilder {
+        self.charset = charset;
+        self
+    }
+
+    pub fn result(mut self, result: impl Into<String>) -> SaoriResponseBuilder {
+        self.result = result.into();
+        self
+    }
+
+    /// Value*の末尾に値を追加する
+    pub fn value(mut self, value: impl Into<String>) -> SaoriResponseBuilder {
+        self.values.push(value.into());
+        self
+    }
+
+    /// Value*の末尾に値をまとめて追加する
+    pub fn values<I, T>(mut self, values: I) -> SaoriResponseBuilder
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.values.extend(values.into_iter().map(Into::into));
+        self
+    }
+
+    /// 返答したモジュールの名前を指定する
+    pub fn sender(mut self, sender: impl Into<String>) -> SaoriResponseBuilder {
+        self.sender = Some(sender.into());
+        self
+    }
+
+    /// 追加のヘッダを設定する。同じ名前のものがある場合は値を置き換える。
+    pub fn header(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> SaoriResponseBuilder {
+        insert_header(&mut self.headers, name.into(), value.into());
+        self
+    }
+
+    /// `X-SAORI-{name}`の追加のヘッダを設定する
+    pub fn x_saori_header(self, name: &str, value: impl Into<String>) -> SaoriResponseBuilder {
+        self.header(SaoriResponse::x_saori_header_name(name), value)
+    }
+
+    /// Charsetで表現できない文字があったときの扱いを指定する
+    ///
+    /// [`SaoriEncodePolicy::Strict`] 以外では、表現できない文字はエラーにならない。
+    pub fn encode_policy(mut self, policy: SaoriEncodePolicy) -> SaoriResponseBuilder {
+        self.encode_policy = policy;
+        self
+    }
+
+    /// 空のValue*の扱いを指定する
+    pub fn empty_values(mut self, empty_values: SaoriEmptyValues) -> SaoriResponseBuilder {
+        self.empty_values = empty_values;
+        self
+    }
+
+    /// エンコード後の大きさ(バイト数)の上限を指定する
+    ///
+    /// 生成したSaoriResponseにも引き継ぐ( [`SaoriResponse::set_max_size`] )。
+    pub fn max_size(mut self, max_size: usize) -> SaoriResponseBuilder {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// 指定したstatusを、内容に合わせて切り替えずにそのまま使うかどうかを指定する
+    ///
+    /// 生成したSaoriResponseにも引き継ぐ( [`SaoriResponse::set_status_locked`] )。
+    /// statusを指定していない場合は効果がない。
+    pub fn status_locked(mut self, status_locked: bool) -> SaoriResponseBuilder {
+        self.status_locked = status_locked;
+        self
+    }
+
+    /// エラーのstatusでもResultとValue*を書き出すかどうかを指定する
+    pub fn content_on_error(mut self, content_on_error: bool) -> SaoriResponseBuilder {
+        self.content_on_error = content_on_error;
+        self
+    }
+
+    /// Resultと各Value*を [`escape_sakura_script`] でエスケープするかどうかを指定する
+    pub fn escape_sakura_script(mut self, escape_sakura_script: bool) -> SaoriResponseBuilder {
+        self.escape_sakura_script = escape_sakura_script;
+        self
+    }
+
+    /// SaoriResponseを生成する。問題があった場合は、見つかったものをすべて返す。
+    pub fn build(mut self) -> Result<SaoriResponse, Vec<SaoriResponseBuildError>> {
+        let mut errors = Vec::new();
+
+        if self.escape_sakura_script {
+            self.result = escape_sakura_script(&self.result).into_owned();
+            for value in self.values.iter_mut() {
+                if let Cow::Owned(escaped) = escape_sakura_script(value) {
+                    *value = escaped;
+                }
+            }
+        }
+
+        let fields = std::iter::once((SaoriResponseField::Result, &self.result)).chain(
+            self.values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (SaoriResponseField::Value(index), value)),
+        );
+        for (field, value) in fields {
+            if has_line_break(value) {
+                errors.push(SaoriResponseBuildError::LineBreak(field));
+            }
+        }
+        if self
+            .sender
+            .as_ref()
+            .is_some_and(|v| is_invalid_header_value(v))
+        {
+            errors.push(SaoriResponseBuildError::LineBreak(
+                SaoriResponseField::Header("Sender".to_string()),
+            ));
+        }
+        for (name, value) in self.headers.iter() {
+            if !is_valid_header_name(name) {
+                errors.push(SaoriResponseBuildError::InvalidHeader(name.clone()));
+            } else if is_invalid_header_value(value) {
+                errors.push(SaoriResponseBuildError::LineBreak(
+                    SaoriResponseField::Header(name.clone()),
+                ));
+            }
+        }
+
+        let status = match self.status {
+            Some(status) => match status.clone().validated() {
+                Ok(status) => Some(status),
+                Err(_) => {
+                    errors.push(SaoriResponseBuildError::InvalidStatus(status));
+                    None
+                }
+            },
+            None => None,
+        };
+        let explicit_ok = status == Some(SaoriStatus::OK);
+        let status_locked = self.status_locked && status.is_some();
+        let mut response = SaoriResponse {
+            version: self.version,
+            status: status.unwrap_or(SaoriStatus::NoContent),
+            result: self.result,
+            values: self.values,
+            charset: self.charset,
+            sender: self.sender,
+            headers: self.headers,
+            encode_policy: self.encode_policy,
+            empty_values: self.empty_values,
+            max_size: None,
+            status_locked,
+            content_on_error: self.content_on_error,
+        };
+        response.on_change_result_and_value();
+
+        // statusを止めている場合はOKのままなので、中身から確かめる
+        let missing_result = if status_locked {
+            response.result.is_empty() && response.values.is_empty()
+        } else {
+            response.status == SaoriStatus::NoContent
+        };
+        if explicit_ok && missing_result {
+            errors.push(SaoriResponseBuildError::MissingResult);
+        }
+
+        let unencodable = match response.encode_policy {
+            SaoriEncodePolicy::Strict => response.find_all_unencodable(),
+            _ => Vec::new(),
+        };
+        // 表現できない文字は`?`に置き換えたものとして、大きさを測る
+        let policy = if unencodable.is_empty() {
+            response.encode_policy
+        } else {
+            SaoriEncodePolicy::Replace
+        };
+        errors.extend(
+            unencodable
+                .into_iter()
+                .map(SaoriResponseBuildError::Unencodable),
+        );
+        match response.unchecked_encoded_len(policy) {
+            Ok(len) if self.max_size.is_some_and(|max_size| len > max_size) => {
+                errors.push(SaoriResponseBuildError::TooLarge(len));
+            }
+            Ok(_) => {}
+            Err(e) => errors.push(SaoriResponseBuildError::EncodeFailed(e)),
+        }
+        response.max_size = self.max_size;
+
+        if errors.is_empty() {
+            Ok(response)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl<S> SaoriTypedResponseBuilder<S> {
+    fn with_state<T>(inner: SaoriResponseBuilder) -> SaoriTypedResponseBuilder<T> {
+        SaoriTypedResponseBuilder {
+            inner,
+            state: std::marker::PhantomData,
+        }
+    }
+
+    fn map(self, f: impl FnOnce(SaoriResponseBuilder) -> SaoriResponseBuilder) -> Self {
+        Self::with_state(f(self.inner))
+    }
+
+    pub fn charset(self, charset: SaoriCharset) -> Self {
+        self.map(|v| v.charset(charset))
+    }
+
+    /// 返答したモジュールの名前を指定する
+    pub fn sender(self, sender: impl Into<String>) -> Self {
+        self.map(|v| v.sender(sender))
+    }
+
+    /// 追加のヘッダを設定する。同じ名前のものがある場合は値を置き換える。
+    pub fn header(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.map(|v| v.header(name, value))
+    }
+
+    /// `X-SAORI-{name}`の追加のヘッダを設定する
+    pub fn x_saori_header(self, name: &str, value: impl Into<String>) -> Self {
+        self.map(|v| v.x_saori_header(name, value))
+    }
+
+    /// Charsetで表現できない文字があったときの扱いを指定する
+    pub fn encode_policy(self, policy: SaoriEncodePolicy) -> Self {
+        self.map(|v| v.encode_policy(policy))
+    }
+
+    /// エンコード後の大きさ(バイト数)の上限を指定する
+    pub fn max_size(self, max_size: usize) -> Self {
+        self.map(|v| v.max_size(max_size))
+    }
+
+    /// SaoriResponseを生成する。問題があった場合は、見つかったものをすべて返す。
+    pub fn build(self) -> Result<SaoriResponse, Vec<SaoriResponseBuildError>> {
+        self.inner.build()
+    }
+}
+
+impl SaoriTypedResponseBuilder<SaoriEmptyState> {
+    /// リクエストのバージョンとCharsetを引き継いだ自身を生成する
+    pub fn from_request(request: &SaoriRequest) -> SaoriTypedResponseBuilder<SaoriEmptyState> {
+        Self::with_state(SaoriResponseBuilder::from_request(request).status(SaoriStatus::NoContent))
+    }
+
+    /// Resultを指定し、OKにする
+    pub fn result(self, result: impl Into<String>) -> SaoriTypedResponseBuilder<SaoriContentState> {
+        Self::with_state(self.inner.status(SaoriStatus::OK).result(result))
+    }
+
+    /// Value*の末尾に値を追加し、OKにする
+    pub fn value(self, value: impl Into<String>) -> SaoriTypedResponseBuilder<SaoriContentState> {
+        Self::with_state(self.inner.status(SaoriStatus::OK).value(value))
+    }
+
+    /// Value*の末尾に値をまとめて追加し、OKにする
+    ///
+    /// 値が1つもない場合は、`build`で [`SaoriResponseBuildError::MissingResult`] になる。
+    pub fn values<I, T>(self, values: I) -> SaoriTypedResponseBuilder<SaoriContentState>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self::with_state(self.inner.status(SaoriStatus::OK).values(values))
+    }
+
+    fn error(self, status: SaoriStatus) -> SaoriTypedResponseBuilder<SaoriErrorState> {
+        Self::with_state(self.inner.status(status))
+    }
+
+    /// Bad Requestにする
+    pub fn bad_request(self) -> SaoriTypedResponseBuilder<SaoriErrorState> {
+        self.error(SaoriStatus::BadRequest)
+    }
+
+    /// Forbiddenにする
+    pub fn forbidden(self) -> SaoriTypedResponseBuilder<SaoriErrorState> {
+        self.error(SaoriStatus::Forbidden)
+    }
+
+    /// Internal Server Errorにする
+    pub fn internal_server_error(self) -> SaoriTypedResponseBuilder<SaoriErrorState> {
+        self.error(SaoriStatus::InternalServerError)
+    }
+
+    /// Not Implementedにする
+    pub fn not_implemented(self) -> SaoriTypedResponseBuilder<SaoriErrorState> {
+        self.error(SaoriStatus::NotImplemented)
+    }
+
+    /// Service Unavailableにする
+    pub fn service_unavailable(self) -> SaoriTypedResponseBuilder<SaoriErrorState> {
+        self.error(SaoriStatus::ServiceUnavailable)
+    }
+}
+
+impl SaoriTypedResponseBuilder<SaoriContentState> {
+    pub fn result(self, result: impl Into<String>) -> Self {
+        self.map(|v| v.result(result))
+    }
+
+    /// Value*の末尾に値を追加する
+    pub fn value(self, value: impl Into<String>) -> Self {
+        self.map(|v| v.value(value))
+    }
+
+    /// Value*の末尾に値をまとめて追加する
+    pub fn values<I, T>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.map(|v| v.values(values))
+    }
+
+    /// 空のValue*の扱いを指定する
+    pub fn empty_values(self, empty_values: SaoriEmptyValues) -> Self {
+        self.map(|v| v.empty_values(empty_values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod saori_response {
+        use super::*;
+        use crate::diagnostics::SaoriLocation;
+
+        mod new_bad_request {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case = SaoriResponse::new_bad_request();
+                assert_eq!(
+                    case,
+                    SaoriResponse {
+                        version: SaoriVersion::V1_0,
+                        status: SaoriStatus::BadRequest,
+                        result: String::new(),
+                        values: vec![],
+                        charset: SaoriCharset::UTF8,
+                        sender: None,
+                        headers: vec![],
+                        encode_policy: SaoriEncodePolicy::Strict,
+                        empty_values: SaoriEmptyValues::Keep,
+                        max_size: None,
+                        status_locked: false,
+                        content_on_error: false,
+                    }
+                );
+            }
+        }
+
+        mod new_bad_request_from_request {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::new_bad_request_from_request(&request);
+                case.set_result("1");
+                assert_eq!(
+                    case.to_string(),
+                    "SAORI/1.0 400 Bad Request\r\nCharset: Shift_JIS\r\n\r\n\0"
+                );
+            }
+        }
+
+        mod from_request {
+            use super::*;
+            use crate::request::SaoriParseOptions;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let case = SaoriResponse::from_request(&request);
+                assert_eq!(
+                    case,
+                    SaoriResponse {
+                        version: SaoriVersion::V1_0,
+                        status: SaoriStatus::NoContent,
+                        result: String::new(),
+                        values: vec![],
+                        charset: SaoriCharset::ShiftJIS,
+                        sender: None,
+                        headers: vec![],
+                        encode_policy: SaoriEncodePolicy::Strict,
+                        empty_values: SaoriEmptyValues::Keep,
+                        max_size: None,
+                        status_locked: false,
+                        content_on_error: false,
+                    }
+                );
+            }
+
+            #[test]
+            fn checking_value_unknown_version() {
+                let request_raw = "EXECUTE SAORI/1.1\r\nCharset: UTF-8\r\n\r\n\0";
+                let options = SaoriParseOptions::new().accept_unknown_version(true);
+                let request =
+                    SaoriRequest::new_with_options(request_raw.as_bytes(), &options).unwrap();
+                let case = SaoriResponse::from_request(&request);
+                assert_eq!(case.version, SaoriVersion::V1_0);
+            }
+        }
+
+        mod builder {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriResponse::builder(&request)
+                    .result("1")
+                    .value("aaa")
+                    .value("bbb")
+                    .build()
+                    .unwrap();
+                assert_eq!(
+                    result.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: 1\r\nValue0: aaa\r\nValue1: bbb\r\n\r\n\0"
+                );
+            }
+        }
+
+        mod ok_with {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriResponse::ok_with(&request, "2", ["aaa", "bbb"]);
+                assert_eq!(
+                    result.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nResult: 2\r\nValue0: aaa\r\nValue1: bbb\r\n\r\n\0"
+                );
+            }
+
+            #[test]
+            fn no_content_when_empty() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriResponse::ok_with(&request, "", Vec::<String>::new());
+                assert_eq!(result.status(), &SaoriStatus::NoContent);
+            }
+        }
+
+        mod no_content {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriResponse::no_content(&request);
+                assert_eq!(
+                    result.to_string(),
+                    "SAORI/1.0 204 No Content\r\nCharset: Shift_JIS\r\n\r\n\0"
+                );
+            }
+        }
+
+        mod from_request_with_values {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriResponse::from_request_with_values(
+                    &request,
+                    ["b", "a"].iter().rev().map(|v| v.to_uppercase()),
+                );
+                assert_eq!(
+                    result.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nValue0: A\r\nValue1: B\r\n\r\n\0"
+                );
+            }
+        }
+
+        mod from_iter {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let result: SaoriResponse = (1..=2).map(|v| v.to_string()).collect();
+                assert_eq!(result.status(), &SaoriStatus::OK);
+                assert_eq!(result.charset, SaoriCharset::UTF8);
+                assert_eq!(result.values(), &["1".to_string(), "2".to_string()]);
+            }
+
+            #[test]
+            fn no_content_when_empty() {
+                let result: SaoriResponse = std::iter::empty::<&str>().collect();
+                assert_eq!(result.status(), &SaoriStatus::NoContent);
+            }
+        }
+
+        mod parse {
+            use super::*;
+
+            #[test]
+            fn round_trip() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::ok_with(&request, "1", ["あいう", "", "b"]);
+                case.set_sender(Some("calc".to_string())).unwrap();
+                case.set_header("X-Elapsed", "10").unwrap();
+                let bytes = case.to_encoded_bytes_u8().unwrap();
+                assert_eq!(SaoriResponse::parse(&bytes).unwrap(), case);
+            }
+
+            #[test]
+            fn failed_when_huge_value_index() {
+                for index in ["18446744073709551615", "4294967295"] {
+                    let case = format!("SAORI/1.0 200 OK\r\nValue{}: a\r\n\r\n\0", index);
+                    assert_eq!(
+                        SaoriResponse::parse(case.as_bytes()),
+                        Err(SaoriResponseParseError::ValueIndexTooLarge(
+                            1,
+                            index.parse().unwrap()
+                        ))
+                    );
+                }
+            }
+
+            #[test]
+            fn checking_value_sparse_values_and_custom_status() {
+                let case =
+                    "SAORI/1.0 299 Partial\r\nCharset: UTF-8\r\nValue2: c\r\nValue0: a\r\n\r\n\0";
+                let result = SaoriResponse::parse(case.as_bytes()).unwrap();
+                assert_eq!(
+                    result.status(),
+                    &SaoriStatus::Custom(299, "Partial".to_string())
+                );
+                assert_eq!(result.charset(), &SaoriCharset::UTF8);
+                assert_eq!(
+                    result.values(),
+                    &["a".to_string(), String::new(), "c".to_string()]
+                );
+            }
+
+            #[test]
+            fn failed_when_empty() {
+                assert_eq!(
+                    SaoriResponse::parse(b"\0"),
+                    Err(SaoriResponseParseError::EmptyResponse)
+                );
+            }
+
+            #[test]
+            fn failed_when_invalid_status_line() {
+                let case = "HTTP/1.1 200 OK\r\nCharset: UTF-8\r\n\r\n\0";
+                assert_eq!(
+                    SaoriResponse::parse(case.as_bytes()),
+                    Err(SaoriResponseParseError::InvalidStatusLine(
+                        "HTTP/1.1 200 OK".to_string()
+                    ))
+                );
+            }
+
+            #[test]
+            fn failed_when_invalid_header() {
+                let case = "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nResult1\r\n\r\n\0";
+                let result = SaoriResponse::parse(case.as_bytes());
+                assert_eq!(
+                    result,
+                    Err(SaoriResponseParseError::InvalidHeader(
+                        2,
+                        "Result1".to_string()
+                    ))
+                );
+                assert_eq!(result.unwrap_err().line(), Some(2));
+            }
+
+            #[test]
+            fn failed_when_unsupported_charset() {
+                let case = "SAORI/1.0 200 OK\r\nCharset: x-unknown\r\n\r\n\0";
+                assert_eq!(
+                    SaoriResponse::parse(case.as_bytes()),
+                    Err(SaoriResponseParseError::Charset(
+                        SaoriRequestCharsetError::UnsupportedCharset
+                    ))
+                );
+            }
+        }
+
+        mod version_response {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "GET Version SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriResponse::version_response(&request, "1.0.0");
+                assert_eq!(
+                    result.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: 1.0.0\r\n\r\n\0"
+                );
+            }
+        }
+
+        mod ok_with_number {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriResponse::ok_with_number(&request, 0.25);
+                assert_eq!(
+                    result.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: 0.25\r\n\r\n\0"
+                );
+            }
+        }
+
+        mod from_request_error {
+            use super::*;
+            use crate::request::{SaoriRequestArgumentError, SaoriRequestCharsetError};
+
+            #[test]
+            fn bad_request_when_malformed_request() {
+                let case = SaoriRequestError::Argument(SaoriRequestArgumentError::NoIndex(
+                    1,
+                    "Argumentx: a".to_string(),
+                ));
+                let result = SaoriResponse::from_request_error(&case);
+                assert_eq!(result.status(), &SaoriStatus::BadRequest);
+            }
+
+            #[test]
+            fn bad_request_when_charset_of_request() {
+                for case in [
+                    SaoriRequestCharsetError::DecodeFailed(0, String::new()),
+                    SaoriRequestCharsetError::UnsupportedCharset,
+                ] {
+                    let result =
+                        SaoriResponse::from_request_error(&SaoriRequestError::Charset(case));
+                    assert_eq!(result.status(), &SaoriStatus::BadRequest);
+                }
+            }
+
+            #[test]
+            fn internal_server_error_when_encode_failed() {
+                let case = SaoriRequestError::Charset(SaoriRequestCharsetError::EncodeFailed);
+                let result = SaoriResponse::from_request_error(&case);
+                assert_eq!(result.status(), &SaoriStatus::InternalServerError);
+            }
+        }
+
+        mod from_request_error_with_reason {
+            use super::*;
+            use crate::request::SaoriRequestArgumentError;
+
+            #[test]
+            fn checking_value() {
+                let case = SaoriRequestError::Argument(SaoriRequestArgumentError::NoIndex(
+                    1,
+                    "Argumentx: a".to_string(),
+                ));
+                let result = SaoriResponse::from_request_error_with_reason(&case);
+                assert_eq!(result.status(), &SaoriStatus::BadRequest);
+                let diagnostic = SaoriDiagnostic::from(&case);
+                assert_eq!(
+                    result.header("X-Error-Reason"),
+                    Some(format!("[1302] {}", diagnostic.message()).as_str())
+                );
+            }
+
+            #[test]
+            fn replace_control_characters_in_reason() {
+                let case = SaoriRequestError::Argument(SaoriRequestArgumentError::NoIndex(
+                    1,
+                    "Argumentx: a\r\nb\0c".to_string(),
+                ));
+                let result = SaoriResponse::from_request_error_with_reason(&case);
+                let reason = result.header("X-Error-Reason").unwrap();
+                assert!(reason.starts_with("[1302] "));
+                assert!(reason.contains("a  b c"));
+                assert!(result.to_encoded_bytes().is_ok());
+            }
+        }
+
+        mod to_encoded_bytes_utf16 {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::from_iter(["あ"]);
+                case.set_charset(SaoriCharset::UTF16LE);
+                let result = case.to_encoded_bytes_u8().unwrap();
+                let expect: Vec<u8> =
+                    "SAORI/1.0 200 OK\r\nCharset: UTF-16LE\r\nValue0: あ\r\n\r\n\0"
+                        .encode_utf16()
+                        .flat_map(u16::to_le_bytes)
+                        .collect();
+                assert_eq!(result, expect);
+                assert!(result.ends_with(b"\r\0\n\0\0\0"));
+
+                let framing = SaoriFraming::new(SaoriLineEnding::CrLf, true, true);
+                let result = case.to_encoded_bytes_with_framing(&framing).unwrap();
+                assert_eq!(&result[..2], &[0xFF_u8 as i8, 0xFE_u8 as i8]);
+            }
+
+            #[test]
+            fn round_trip_through_parse() {
+                let mut case = SaoriResponse::from_iter(["あ"]);
+                case.set_charset(SaoriCharset::UTF16BE);
+                let bytes = case.to_encoded_bytes_u8().unwrap();
+                let result = SaoriResponse::parse(&bytes).unwrap();
+                assert_eq!(result.charset(), &SaoriCharset::UTF16BE);
+                assert_eq!(result.values(), &["あ".to_string()]);
+            }
+        }
+
+        mod set_max_size {
+            use super::*;
+
+            fn response() -> SaoriResponse {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut response = SaoriResponse::from_request(&request);
+                response.set_result("1");
+                response
+            }
+
+            #[test]
+            fn checking_value() {
+                let mut case = response();
+                let size = case.to_encoded_bytes().unwrap().len();
+                case.set_max_size(Some(size));
+                assert_eq!(case.to_encoded_bytes().unwrap().len(), size);
+                assert_eq!(case.into_encoded_bytes().unwrap().len(), size);
+            }
+
+            #[test]
+            fn failed_when_too_large() {
+                let mut case = response();
+                let size = case.to_encoded_bytes().unwrap().len();
+                case.set_max_size(Some(size - 1));
+                assert_eq!(
+                    case.to_encoded_bytes(),
+                    Err(SaoriResponseError::TooLarge(size))
+                );
+                assert_eq!(
+                    case.to_encoded_bytes_u8(),
+                    Err(SaoriResponseError::TooLarge(size))
+                );
+
+                let mut writer = Vec::new();
+                assert_eq!(
+                    case.write_to(&mut writer),
+                    Err(SaoriResponseError::TooLarge(size))
+                );
+                assert!(writer.is_empty());
+
+                assert_eq!(
+                    case.into_encoded_bytes(),
+                    Err(SaoriResponseError::TooLarge(size))
+                );
+            }
+        }
+
+        mod serialized_len {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request_with_values(&request, ["あ", "", "b"]);
+                case.set_result("結果");
+                case.set_sender(Some("sender".to_string())).unwrap();
+                case.set_header("X-Test", "値").unwrap();
+                assert_eq!(case.serialized_len(), case.to_string().len());
+
+                case.set_status(SaoriStatus::Custom(299, "Custom".to_string()));
+                assert_eq!(case.serialized_len(), case.to_string().len());
+            }
+
+            #[test]
+            fn checking_value_bad_request() {
+                let case = SaoriResponse::new_bad_request();
+                assert_eq!(case.serialized_len(), case.to_string().len());
+            }
+        }
+
+        mod error_bytes_with {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let result =
+                    SaoriResponse::error_bytes_with(&SaoriCharset::ShiftJIS, Some("失敗\r\nした"));
+                let expect = SaoriCharset::ShiftJIS
+                    .to_encoding()
+                    .encode("SAORI/1.0 500 Internal Server Error\r\nCharset: Shift_JIS\r\nX-Error-Reason: 失敗  した\r\n\r\n\0")
+                    .0
+                    .into_owned();
+                assert_eq!(result, SaoriResponse::into_ffi_bytes(expect));
+            }
+
+            #[test]
+            fn checking_value_without_reason() {
+                let result = SaoriResponse::error_bytes_with(&SaoriCharset::UTF8, None);
+                assert_eq!(result, SaoriResponse::error_bytes());
+            }
+
+            #[test]
+            fn replace_unencodable_reason() {
+                let result =
+                    SaoriResponse::error_bytes_with(&SaoriCharset::ShiftJIS, Some("a\u{1F600}"));
+                let expect = "SAORI/1.0 500 Internal Server Error\r\nCharset: Shift_JIS\r\nX-Error-Reason: a?\r\n\r\n\0";
+                assert_eq!(
+                    result,
+                    SaoriResponse::into_ffi_bytes(expect.as_bytes().to_vec())
+                );
+            }
+
+            #[test]
+            fn replace_control_characters_in_reason() {
+                let result =
+                    SaoriResponse::error_bytes_with(&SaoriCharset::UTF8, Some("a\0b\x1bc\td"));
+                let expect = "SAORI/1.0 500 Internal Server Error\r\nCharset: UTF-8\r\nX-Error-Reason: a b c d\r\n\r\n\0";
+                assert_eq!(
+                    result,
+                    SaoriResponse::into_ffi_bytes(expect.as_bytes().to_vec())
+                );
+            }
+        }
+
+        mod from {
+            use super::*;
+
+            fn handle(request: SaoriRequest) -> SaoriResponse {
+                SaoriResponse::ok_with(&request, "1", Vec::<String>::new())
+            }
+
+            #[test]
+            fn bad_request_when_parse_failed() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nArgumentx: a\r\n\r\n\0";
+                let result = SaoriRequest::new(request_raw.as_bytes())
+                    .map_or_else(SaoriResponse::from, handle);
+                assert_eq!(result.status(), &SaoriStatus::BadRequest);
+            }
+
+            #[test]
+            fn ok_when_parse_succeeded() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let result = SaoriRequest::new(request_raw.as_bytes())
+                    .map_or_else(SaoriResponse::from, handle);
+                assert_eq!(result.status(), &SaoriStatus::OK);
+            }
+        }
+
+        mod set_result {
+            use super::*;
+
+            #[test]
+            fn checking_value_no_empty() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                assert_eq!(case.status, SaoriStatus::NoContent);
+                let case_result = "aaa".to_string();
+                case.set_result(case_result.clone());
+                assert_eq!(
+                    case,
+                    SaoriResponse {
+                        version: SaoriVersion::V1_0,
+                        status: SaoriStatus::OK,
+                        result: case_result.clone(),
+                        values: vec![],
+                        charset: SaoriCharset::ShiftJIS,
+                        sender: None,
+                        headers: vec![],
+                        encode_policy: SaoriEncodePolicy::Strict,
+                        empty_values: SaoriEmptyValues::Keep,
+                        max_size: None,
+                        status_locked: false,
+                        content_on_error: false,
+                    }
+                );
+            }
+
+            #[test]
+            fn checking_value_empty() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                assert_eq!(case.status, SaoriStatus::NoContent);
+                let case_result = "".to_string();
+                case.set_result(case_result.clone());
+                assert_eq!(
+                    case,
+                    SaoriResponse {
+                        version: SaoriVersion::V1_0,
+                        status: SaoriStatus::NoContent,
+                        result: case_result.clone(),
+                        values: vec![],
+                        charset: SaoriCharset::ShiftJIS,
+                        sender: None,
+                        headers: vec![],
+                        encode_policy: SaoriEncodePolicy::Strict,
+                        empty_values: SaoriEmptyValues::Keep,
+                        max_size: None,
+                        status_locked: false,
+                        content_on_error: false,
+                    }
+                );
+            }
+        }
+
+        mod set_charset {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::ok_with(&request, "\u{1F600}", Vec::<String>::new());
+                assert!(case.to_encoded_bytes().is_err());
+                case.set_charset(SaoriCharset::UTF8);
+                assert_eq!(case.charset(), &SaoriCharset::UTF8);
+                assert_eq!(
+                    case.to_encoded_bytes_u8().unwrap(),
+                    "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nResult: \u{1F600}\r\n\r\n\0".as_bytes()
+                );
+            }
+        }
+
+        mod set_sender {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::ok_with(&request, "1", Vec::<String>::new());
+                case.set_header("X-Elapsed", "10").unwrap();
+                case.set_sender(Some("calc".to_string())).unwrap();
+                assert_eq!(case.sender(), Some("calc"));
+                assert_eq!(
+                    case.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nSender: calc\r\nResult: 1\r\nX-Elapsed: 10\r\n\r\n\0"
+                );
+                assert_eq!(
+                    case.into_encoded_bytes().unwrap(),
+                    SaoriResponse::into_ffi_bytes(
+                        b"SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nSender: calc\r\nResult: 1\r\nX-Elapsed: 10\r\n\r\n\0".to_vec()
+                    )
+                );
+            }
+
+            #[test]
+            fn failed_when_line_break() {
+                let mut case = SaoriResponse::new_bad_request();
+                assert_eq!(
+                    case.set_sender(Some("a\r\nResult: 1".to_string())),
+                    Err(SaoriResponseError::InvalidHeader("Sender".to_string()))
+                );
+                assert_eq!(
+                    case.set_header("sender", "calc"),
+                    Err(SaoriResponseError::InvalidHeader("sender".to_string()))
+                );
+                assert_eq!(case.sender(), None);
+            }
+        }
+
+        mod set_result_display {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::NoContent);
+                case.set_result_display(42);
+                assert_eq!(case.result(), "42");
+                assert_eq!(case.status(), &SaoriStatus::OK);
+                case.set_result_display(1.5);
+                assert_eq!(case.result(), "1.5");
+            }
+        }
+
+        mod set_value_at {
+            use super::*;
+
+            #[test]
+            fn checking_value_inner() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                let case_values = vec!["aaa".to_string(), "bbb".to_string()];
+                case.values = case_values;
+                assert_eq!(case.status, SaoriStatus::NoContent);
+                let case_value = "bbb002".to_string();
+                case.set_value_at(1, case_value);
+                assert_eq!(
+                    case,
+                    SaoriResponse {
+                        version: SaoriVersion::V1_0,
+                        status: SaoriStatus::OK,
+                        result: String::new(),
+                        values: vec!["aaa".to_string(), "bbb002".to_string()],
+                        charset: SaoriCharset::ShiftJIS,
+                        sender: None,
+                        headers: vec![],
+                        encode_policy: SaoriEncodePolicy::Strict,
+                        empty_values: SaoriEmptyValues::Keep,
+                        max_size: None,
+                        status_locked: false,
+                        content_on_error: false,
+                    }
+                );
+            }
+
+            #[test]
+            fn checking_value_outer() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                assert_eq!(case.status, SaoriStatus::NoContent);
+                let case_value = "bbb002".to_string();
+                case.set_value_at(1, case_value);
+                assert_eq!(
+                    case,
+                    SaoriResponse {
+                        version: SaoriVersion::V1_0,
+                        status: SaoriStatus::OK,
+                        result: String::new(),
+                        values: vec!["".to_string(), "bbb002".to_string()],
+                        charset: SaoriCharset::ShiftJIS,
+                        sender: None,
+                        headers: vec![],
+                        encode_policy: SaoriEncodePolicy::Strict,
+                        empty_values: SaoriEmptyValues::Keep,
+                        max_size: None,
+                        status_locked: false,
+                        content_on_error: false,
+                    }
+                );
+            }
+        }
+
+        mod set_value_display_at {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::NoContent);
+                case.set_value_display_at(1, -3);
+                case.set_value_display_at(0, 'a');
+                assert_eq!(case.values(), &["a".to_string(), "-3".to_string()]);
+                assert_eq!(case.status(), &SaoriStatus::OK);
+            }
+        }
+
+        mod set_values {
+            use super::*;
+
+            #[test]
+            fn checking_value_no_empty() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                assert_eq!(case.status, SaoriStatus::NoContent);
+                let case_values = vec!["aaa".to_string(), "bbb".to_string()];
+                case.set_values(case_values.clone());
+                assert_eq!(
+                    case,
+                    SaoriResponse {
+                        version: SaoriVersion::V1_0,
+                        status: SaoriStatus::OK,
+                        result: String::new(),
+                        values: case_values.clone(),
+                        charset: SaoriCharset::ShiftJIS,
+                        sender: None,
+                        headers: vec![],
+                        encode_policy: SaoriEncodePolicy::Strict,
+                        empty_values: SaoriEmptyValues::Keep,
+                        max_size: None,
+                        status_locked: false,
+                        content_on_error: false,
+                    }
+                );
+            }
+
+            #[test]
+            fn checking_value_empty() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                assert_eq!(case.status, SaoriStatus::NoContent);
+                let case_values = vec![];
+                case.set_values(case_values.clone());
+                assert_eq!(
+                    case,
+                    SaoriResponse {
+                        version: SaoriVersion::V1_0,
+                        status: SaoriStatus::NoContent,
+                        result: String::new(),
+                        values: case_values.clone(),
+                        charset: SaoriCharset::ShiftJIS,
+                        sender: None,
+                        headers: vec![],
+                        encode_policy: SaoriEncodePolicy::Strict,
+                        empty_values: SaoriEmptyValues::Keep,
+                        max_size: None,
+                        status_locked: false,
+                        content_on_error: false,
+                    }
+                );
+            }
+        }
+
+        mod with_result {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
                 let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
                 let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut template = SaoriResponse::from_request(&request);
+                template.set_sender(Some("calc".to_string())).unwrap();
+
+                let result = template.clone().with_result("1").with_value("a");
+                assert_eq!(result.status(), &SaoriStatus::OK);
+                assert_eq!(result.charset(), &SaoriCharset::ShiftJIS);
+                assert_eq!(result.sender(), Some("calc"));
+                assert_eq!(result.result(), "1");
+                assert_eq!(result.values(), &["a".to_string()]);
+
+                let result = template.clone().with_status(SaoriStatus::Forbidden);
+                assert_eq!(result.status(), &SaoriStatus::Forbidden);
+                assert_eq!(template.status(), &SaoriStatus::NoContent);
+            }
+        }
+
+        mod try_from {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case = SaoriResponse::from_iter(["a"]);
+                let result: Vec<i8> = (&case).try_into().unwrap();
+                assert_eq!(result, case.to_encoded_bytes().unwrap());
+                let result: Vec<u8> = (&case).try_into().unwrap();
+                assert_eq!(result, case.to_encoded_bytes_u8().unwrap());
+            }
+
+            #[test]
+            fn failed_when_unencodable() {
+                let mut case = SaoriResponse::from_iter(["\u{1F600}"]);
+                case.set_charset(SaoriCharset::ShiftJIS);
+                assert!(matches!(
+                    Vec::<u8>::try_from(&case),
+                    Err(SaoriResponseError::Unencodable(_))
+                ));
+            }
+        }
+
+        mod set_content_on_error {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::InternalServerError);
+                case.set_result("failed");
+                case.push_value("detail");
+                assert_eq!(
+                    case.to_string(),
+                    "SAORI/1.0 500 Internal Server Error\r\nCharset: UTF-8\r\n\r\n\0"
+                );
+
+                case.set_content_on_error(true);
+                let expect = "SAORI/1.0 500 Internal Server Error\r\nCharset: UTF-8\r\nResult: failed\r\nValue0: detail\r\n\r\n\0";
+                assert_eq!(case.to_string(), expect);
+                assert_eq!(
+                    case.into_encoded_bytes().unwrap(),
+                    SaoriResponse::into_ffi_bytes(expect.as_bytes().to_vec())
+                );
+            }
+
+            #[test]
+            fn failed_when_unencodable() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_charset(SaoriCharset::ShiftJIS);
+                case.set_result("\u{1F600}");
+                assert!(case.to_encoded_bytes().is_ok());
+
+                case.set_content_on_error(true);
+                assert!(matches!(
+                    case.to_encoded_bytes(),
+                    Err(SaoriResponseError::Unencodable(_))
+                ));
+            }
+        }
+
+        mod set_status {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::Custom(200, "OK".to_string()));
+                assert_eq!(case.status(), &SaoriStatus::OK);
+                case.set_status(SaoriStatus::Custom(299, "Partial".to_string()));
+                assert_eq!(
+                    case.status(),
+                    &SaoriStatus::Custom(299, "Partial".to_string())
+                );
+            }
+
+            #[test]
+            fn failed_to_encode_when_invalid_custom() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::Custom(7, "x".to_string()));
+                assert_eq!(
+                    case.to_encoded_bytes(),
+                    Err(SaoriResponseError::InvalidStatus(7))
+                );
+                case.set_status(SaoriStatus::Custom(299, "Odd\r\nX-Evil: 1".to_string()));
+                let expect =
+                    SaoriResponseError::InvalidStatusReason("Odd\r\nX-Evil: 1".to_string());
+                assert_eq!(case.to_encoded_bytes().unwrap_err(), expect);
+                assert_eq!(case.to_encoded_bytes_u8().unwrap_err(), expect);
+                assert_eq!(case.write_to(&mut Vec::new()).unwrap_err(), expect);
+            }
+
+            #[test]
+            fn failed_when_invalid_custom_in_builder() {
+                let case = SaoriStatus::Custom(299, "Odd\r\nX-Evil: 1".to_string());
+                let result = SaoriResponseBuilder::new().status(case.clone()).build();
+                assert_eq!(
+                    result,
+                    Err(vec![SaoriResponseBuildError::InvalidStatus(case)])
+                );
+            }
+        }
+
+        mod try_set_status {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.try_set_status(SaoriStatus::Custom(200, "OK".to_string()))
+                    .unwrap();
+                assert_eq!(case.status(), &SaoriStatus::OK);
+            }
+
+            #[test]
+            fn failed_when_invalid_custom() {
+                let mut case = SaoriResponse::new_bad_request();
+                assert_eq!(
+                    case.try_set_status(SaoriStatus::Custom(7, "x".to_string())),
+                    Err(SaoriResponseError::InvalidStatus(7))
+                );
+                assert_eq!(
+                    case.try_set_status(SaoriStatus::Custom(299, "Odd\r\nX-Evil: 1".to_string())),
+                    Err(SaoriResponseError::InvalidStatusReason(
+                        "Odd\r\nX-Evil: 1".to_string()
+                    ))
+                );
+                assert_eq!(case.status(), &SaoriStatus::BadRequest);
+            }
+        }
+
+        mod set_status_locked {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::from_iter(["a"]);
+                case.set_status_locked(true);
+                case.set_value_at(0, "");
+                assert_eq!(case.status(), &SaoriStatus::OK);
+
+                case.set_status(SaoriStatus::NoContent);
+                case.set_result("diagnostic");
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+
+                case.set_status_locked(false);
+                assert_eq!(case.status(), &SaoriStatus::OK);
+            }
+        }
+
+        mod set_values_from_iter {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::from_iter(["old", "old"]);
+                case.set_values_from_iter((1..=3).map(|v| v * 10));
+                assert_eq!(case.status(), &SaoriStatus::OK);
+                assert_eq!(
+                    case.values(),
+                    &["10".to_string(), "20".to_string(), "30".to_string()]
+                );
+            }
+
+            #[test]
+            fn no_content_when_empty() {
+                let mut case = SaoriResponse::from_iter(["old"]);
+                case.set_values_from_iter(Vec::<f64>::new());
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+            }
+        }
+
+        mod set_values_joined {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::OK);
+                case.set_values_joined([1, 2, 3].map(|v| v.to_string()), "\x01");
+                assert_eq!(case.values(), &["1\x012\x013".to_string()]);
+                assert_eq!(case.value_byte1_list(0), vec!["1", "2", "3"]);
+            }
+
+            #[test]
+            fn round_trip_through_parse() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::OK);
+                case.set_values_joined(["a", "", "b"], "\x01");
+                let bytes = case.to_encoded_bytes_u8().unwrap();
+                let result = SaoriResponse::parse(&bytes).unwrap();
+                assert_eq!(result.value_byte1_list(0), vec!["a", "", "b"]);
+            }
+
+            #[test]
+            fn empty_when_no_value() {
+                let case = SaoriResponse::new_bad_request();
+                assert!(case.value_byte1_list(0).is_empty());
+            }
+        }
+
+        mod set_empty_values {
+            use super::*;
+
+            fn response() -> SaoriResponse {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                SaoriResponse::from_request_with_values(&request, ["", "a", ""])
+            }
+
+            #[test]
+            fn checking_value_keep() {
+                let mut case = response();
+                assert_eq!(
+                    case.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nValue0: \r\nValue1: a\r\nValue2: \r\n\r\n\0"
+                );
+                case.set_value_at(1, "");
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+            }
+
+            #[test]
+            fn checking_value_always() {
+                let mut case = response();
+                case.set_empty_values(SaoriEmptyValues::Always);
+                case.set_value_at(1, "");
+                assert_eq!(
+                    case.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nValue0: \r\nValue1: \r\nValue2: \r\n\r\n\0"
+                );
+            }
+
+            #[test]
+            fn checking_value_skip() {
+                let mut case = response();
+                case.set_empty_values(SaoriEmptyValues::Skip);
+                let expect = "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nValue1: a\r\n\r\n\0";
+                assert_eq!(case.to_string(), expect);
+                assert_eq!(
+                    case.into_encoded_bytes().unwrap(),
+                    SaoriResponse::into_ffi_bytes(expect.as_bytes().to_vec())
+                );
+            }
+        }
+
+        mod values_mut {
+            use super::*;
+
+            #[test]
+            fn update_status_when_dropped() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.set_values(vec!["\\0a".to_string(), "b".to_string()]);
+                for value in case.values_mut().iter_mut() {
+                    *value = value.replace('\\', "\\\\");
+                }
+                assert_eq!(case.values(), &["\\\\0a".to_string(), "b".to_string()]);
+                assert_eq!(case.status(), &SaoriStatus::OK);
+
+                case.values_mut().iter_mut().for_each(|v| v.clear());
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+            }
+        }
+
+        mod clear_values {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.set_values(vec!["aaa".to_string()]);
+                case.clear_values();
+                assert!(case.values().is_empty());
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+            }
+
+            #[test]
+            fn keep_ok_when_result_exists() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.set_result("1".to_string());
+                case.set_values(vec!["aaa".to_string()]);
+                case.clear_values();
+                assert_eq!(case.status(), &SaoriStatus::OK);
+            }
+        }
+
+        mod push_value {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.push_value("");
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+                case.push_value("aaa");
+                assert_eq!(case.status(), &SaoriStatus::OK);
+                assert_eq!(case.values(), &["".to_string(), "aaa".to_string()]);
+            }
+        }
+
+        mod push_value_display {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::NoContent);
+                for v in [3u8, 1, 4] {
+                    case.push_value_display(v);
+                }
+                assert_eq!(
+                    case.values(),
+                    &["3".to_string(), "1".to_string(), "4".to_string()]
+                );
+                assert_eq!(case.status(), &SaoriStatus::OK);
+            }
+        }
+
+        mod extend_values {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.push_value("aaa");
+                case.extend_values((1..=2).map(|v| v.to_string()));
+                assert_eq!(case.status(), &SaoriStatus::OK);
+                assert_eq!(
+                    case.values(),
+                    &["aaa".to_string(), "1".to_string(), "2".to_string()]
+                );
+            }
+
+            #[test]
+            fn no_content_when_empty() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.extend_values(Vec::<String>::new());
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+            }
+        }
+
+        mod into_values {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_values(vec!["aaa".to_string(), "bbb".to_string()]);
+                assert_eq!(
+                    case.into_values(),
+                    vec!["aaa".to_string(), "bbb".to_string()]
+                );
+            }
+        }
+
+        mod set_header {
+            use super::*;
+
+            #[test]
+            fn replace_when_same_name() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_header("X-Elapsed", "10").unwrap();
+                case.set_header("X-Warning", "slow").unwrap();
+                case.set_header("x-elapsed", "12").unwrap();
+                assert_eq!(
+                    case.headers(),
+                    &[
+                        ("X-Elapsed".to_string(), "12".to_string()),
+                        ("X-Warning".to_string(), "slow".to_string())
+                    ]
+                );
+                assert_eq!(case.header("X-ELAPSED"), Some("12"));
+            }
+
+            #[test]
+            fn failed_when_invalid_name_or_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                for name in ["", "Result", "value3", "X Elapsed", "X:Elapsed"] {
+                    assert_eq!(
+                        case.set_header(name, "1"),
+                        Err(SaoriResponseError::InvalidHeader(name.to_string()))
+                    );
+                }
+                assert_eq!(
+                    case.set_header("X-Elapsed", "1\r\nResult: 2"),
+                    Err(SaoriResponseError::InvalidHeader("X-Elapsed".to_string()))
+                );
+                assert!(case.set_header("ValueX", "1").is_ok());
+                assert_eq!(case.headers().len(), 1);
+            }
+
+            #[test]
+            fn failed_when_control_character_in_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                for value in ["a\0b", "a\x1bb", "a\x7fb"] {
+                    assert_eq!(
+                        case.set_header("X-A", value),
+                        Err(SaoriResponseError::InvalidHeader("X-A".to_string()))
+                    );
+                }
+                assert!(case.set_header("X-A", "a\tb").is_ok());
+                assert_eq!(
+                    case.set_sender(Some("a\0b".to_string())),
+                    Err(SaoriResponseError::InvalidHeader("Sender".to_string()))
+                );
+            }
+        }
+
+        mod remove_header {
+            use super::*;
+
+            #[test]
+            fn keep_order_of_rest() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_header("X-A", "1").unwrap();
+                case.set_header("X-B", "2").unwrap();
+                case.set_header("X-C", "3").unwrap();
+                assert_eq!(case.remove_header("x-b"), Some("2".to_string()));
+                assert_eq!(case.remove_header("X-B"), None);
+                case.set_header("X-B", "4").unwrap();
+                let names: Vec<&str> = case.headers().iter().map(|(k, _)| k.as_str()).collect();
+                assert_eq!(names, vec!["X-A", "X-C", "X-B"]);
+            }
+        }
+
+        mod x_saori_header {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_x_saori_header("Elapsed", "10").unwrap();
+                case.set_x_saori_header("x-saori-Elapsed", "12").unwrap();
+                assert_eq!(
+                    case.headers(),
+                    &[("X-SAORI-Elapsed".to_string(), "12".to_string())]
+                );
+                assert_eq!(case.x_saori_header("Elapsed"), Some("12"));
+            }
+        }
+
+        mod on_change_result_and_value {
+            use super::*;
+
+            #[test]
+            fn no_content_when_value_actually_empty_and_no_result() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::OK);
+                assert_eq!(case.status(), &SaoriStatus::OK);
+                let values = vec![String::new(), String::new(), String::new()];
+                case.set_values(values);
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+            }
+
+            #[test]
+            fn no_content_when_empty_value_and_no_result() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::OK);
+                assert_eq!(case.status(), &SaoriStatus::OK);
+                let values = vec![];
+                case.set_values(values);
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+            }
+
+            #[test]
+            fn ok_when_some_values() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::NoContent);
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+                let values = vec![String::new(), String::new(), String::new()];
+                case.set_values(values);
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+                case.set_value_at(3, "aaa".to_string());
+                assert_eq!(case.status(), &SaoriStatus::OK);
+            }
+
+            #[test]
+            fn ok_when_result_is_some() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::NoContent);
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+                let values = vec![String::new(), String::new(), String::new()];
+                case.set_values(values);
+                assert_eq!(case.status(), &SaoriStatus::NoContent);
+                case.set_result("aaa".to_string());
+                assert_eq!(case.status(), &SaoriStatus::OK);
+            }
+        }
+
+        mod to_encoded_bytes {
+            use encoding_rs::SHIFT_JIS;
+
+            use super::*;
+
+            #[test]
+            fn failed_when_line_break_in_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
                 let mut case = SaoriResponse::from_request(&request);
-                assert_eq!(case.status, SaoriStatus::NoContent);
-                let case_result = "aaa".to_string();
-                case.set_result(case_result.clone());
+                case.set_result("x\r\nX-Evil: 1");
                 assert_eq!(
-                    case,
-                    SaoriResponse {
-                        version: SaoriVersion::V1_0,
-                        status: SaoriStatus::OK,
-                        result: case_result.clone(),
-                        values: vec![],
-                        charset: SaoriCharset::ShiftJIS
-                    }
+                    case.to_encoded_bytes(),
+                    Err(SaoriResponseError::LineBreak(SaoriResponseField::Result))
+                );
+
+                let mut case = SaoriResponse::from_request(&request);
+                case.push_value("a");
+                case.extend_values(["b\0c"]);
+                assert_eq!(
+                    case.to_encoded_bytes(),
+                    Err(SaoriResponseError::LineBreak(SaoriResponseField::Value(1)))
+                );
+                assert_eq!(
+                    case.into_encoded_bytes(),
+                    Err(SaoriResponseError::LineBreak(SaoriResponseField::Value(1)))
                 );
             }
 
             #[test]
-            fn checking_value_empty() {
+            fn success_when_valid_request() {
                 let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
                 let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
                 let mut case = SaoriResponse::from_request(&request);
-                assert_eq!(case.status, SaoriStatus::NoContent);
-                let case_result = "".to_string();
-                case.set_result(case_result.clone());
+                case.set_result("1".to_string());
+                case.set_values(vec!["aaa".to_string(), "bbb".to_string()]);
+                let result = case.to_encoded_bytes().unwrap();
+                let expect_raw =
+                    "SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: 1\r\nValue0: aaa\r\nValue1: bbb\r\n\r\n\0";
+                let expect = SHIFT_JIS.encode(expect_raw).0.into_owned();
+                let expect: Vec<i8> = expect.iter().map(|v| *v as i8).collect();
+                assert_eq!(result, expect);
+            }
+
+            #[test]
+            fn failed_when_unencodable_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.set_result("1".to_string());
+                case.set_values(vec!["aaa".to_string(), "あい\u{1F600}".to_string()]);
+                let result = case.to_encoded_bytes();
                 assert_eq!(
-                    case,
-                    SaoriResponse {
-                        version: SaoriVersion::V1_0,
-                        status: SaoriStatus::NoContent,
-                        result: case_result.clone(),
-                        values: vec![],
-                        charset: SaoriCharset::ShiftJIS
-                    }
+                    result,
+                    Err(SaoriResponseError::Unencodable(SaoriResponseEncodeError {
+                        field: SaoriResponseField::Value(1),
+                        character: '\u{1F600}',
+                        offset: 6,
+                        charset: SaoriCharset::ShiftJIS,
+                    }))
                 );
             }
         }
 
-        mod set_value_at {
+        mod serialized_headers {
             use super::*;
 
             #[test]
-            fn checking_value_inner() {
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::ok_with(&request, "1", ["a", "b"]);
+                case.set_header("X-Elapsed", "10").unwrap();
+                case.set_sender(Some("calc".to_string())).unwrap();
+                let result: Vec<(String, String)> = case
+                    .serialized_headers()
+                    .into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect();
+                let expect: Vec<(String, String)> = [
+                    ("Charset", "UTF-8"),
+                    ("Sender", "calc"),
+                    ("Result", "1"),
+                    ("Value0", "a"),
+                    ("Value1", "b"),
+                    ("X-Elapsed", "10"),
+                ]
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+                assert_eq!(result, expect);
+
+                let lines: String = result
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}\r\n", k, v))
+                    .collect();
+                assert_eq!(
+                    case.to_string(),
+                    format!("SAORI/1.0 200 OK\r\n{}\r\n\0", lines)
+                );
+            }
+
+            #[test]
+            fn checking_value_without_content_when_not_ok() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_result("1");
+                case.set_header("X-Warning", "a").unwrap();
+                let headers = case.serialized_headers();
+                let result: Vec<&str> = headers.iter().map(|(k, _)| k.as_ref()).collect();
+                assert_eq!(result, vec!["Charset", "X-Warning"]);
+            }
+        }
+
+        mod write_to {
+            use super::*;
+
+            struct FailingWriter;
+
+            impl std::io::Write for FailingWriter {
+                fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                    Err(std::io::ErrorKind::BrokenPipe.into())
+                }
+
+                fn flush(&mut self) -> std::io::Result<()> {
+                    Ok(())
+                }
+            }
+
+            #[test]
+            fn success_when_valid_response() {
                 let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
                 let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
-                let mut case = SaoriResponse::from_request(&request);
-                let case_values = vec!["aaa".to_string(), "bbb".to_string()];
-                case.values = case_values;
-                assert_eq!(case.status, SaoriStatus::NoContent);
-                let case_value = "bbb002".to_string();
-                case.set_value_at(1, case_value);
+                let mut case = SaoriResponse::ok_with(&request, "1", ["あいう", "bbb"]);
+                case.set_header("X-Elapsed", "10").unwrap();
+                let mut result = Vec::new();
+                let written = case.write_to(&mut result).unwrap();
+                let expect: Vec<u8> = case
+                    .to_encoded_bytes()
+                    .unwrap()
+                    .iter()
+                    .map(|v| *v as u8)
+                    .collect();
+                assert_eq!(result, expect);
+                assert_eq!(written, expect.len());
+            }
+
+            #[test]
+            fn failed_when_unencodable_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let case = SaoriResponse::ok_with(&request, "1", ["\u{1F600}"]);
+                let mut result = Vec::new();
+                assert!(matches!(
+                    case.write_to(&mut result),
+                    Err(SaoriResponseError::Unencodable(_))
+                ));
+            }
+
+            #[test]
+            fn failed_when_write_failed() {
+                let case = SaoriResponse::new_bad_request();
                 assert_eq!(
-                    case,
-                    SaoriResponse {
-                        version: SaoriVersion::V1_0,
-                        status: SaoriStatus::OK,
-                        result: String::new(),
-                        values: vec!["aaa".to_string(), "bbb002".to_string()],
-                        charset: SaoriCharset::ShiftJIS
-                    }
+                    case.write_to(&mut FailingWriter),
+                    Err(SaoriResponseError::WriteFailed(
+                        std::io::ErrorKind::BrokenPipe
+                    ))
                 );
             }
+        }
+
+        mod to_encoded_bytes_u8 {
+            use super::*;
 
             #[test]
-            fn checking_value_outer() {
+            fn checking_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let case = SaoriResponse::ok_with(&request, "1", ["aaa"]);
+                assert_eq!(
+                    case.to_encoded_bytes_u8().unwrap(),
+                    b"SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nResult: 1\r\nValue0: aaa\r\n\r\n\0"
+                );
+            }
+        }
+
+        mod into_ffi_bytes {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let case = vec![0x41u8, 0x80, 0xFF, 0x00];
+                let ptr = case.as_ptr() as usize;
+                let result = SaoriResponse::into_ffi_bytes(case);
+                assert_eq!(result, vec![0x41i8, -128, -1, 0]);
+                assert_eq!(result.as_ptr() as usize, ptr);
+            }
+        }
+
+        mod into_encoded_bytes {
+            use super::*;
+
+            #[test]
+            fn success_when_valid_response() {
                 let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
                 let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
-                let mut case = SaoriResponse::from_request(&request);
-                assert_eq!(case.status, SaoriStatus::NoContent);
-                let case_value = "bbb002".to_string();
-                case.set_value_at(1, case_value);
+                let mut case = SaoriResponse::ok_with(&request, "1", ["あいう", "", "bbb"]);
+                case.set_header("X-Elapsed", "10").unwrap();
+                let expect = case.to_encoded_bytes().unwrap();
+                assert_eq!(case.into_encoded_bytes().unwrap(), expect);
+            }
+
+            #[test]
+            fn failed_when_unencodable_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let case = SaoriResponse::ok_with(&request, "1", ["aaa", "b\u{1F600}"]);
+                let expect = case.to_encoded_bytes();
+                assert!(expect.is_err());
+                assert_eq!(case.into_encoded_bytes(), expect);
+            }
+
+            #[test]
+            fn same_as_other_serializations() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::ok_with(&request, "失敗", ["あいう", "", "bbb"]);
+                case.set_status(SaoriStatus::InternalServerError);
+                case.set_content_on_error(true);
+                case.set_empty_values(SaoriEmptyValues::Skip);
+                case.set_sender(Some("materia".to_string())).unwrap();
+                case.set_header("X-Elapsed", "10").unwrap();
+
+                let expect = case.to_encoded_bytes_u8().unwrap();
+                let mut written = Vec::new();
+                case.write_to(&mut written).unwrap();
+                let mut buffer = Vec::new();
+                case.to_encoded_bytes_into(&mut buffer).unwrap();
+                let framed = case
+                    .to_encoded_bytes_with_framing(&SaoriFraming::default())
+                    .unwrap();
+                let consumed = case.into_encoded_bytes().unwrap();
+
+                assert_eq!(written, expect);
+                assert_eq!(buffer, expect);
+                assert_eq!(framed, SaoriResponse::into_ffi_bytes(expect.clone()));
+                assert_eq!(consumed, SaoriResponse::into_ffi_bytes(expect));
+            }
+        }
+
+        mod to_encoded_bytes_into {
+            use super::*;
+
+            #[test]
+            fn reuse_buffer() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut buffer = Vec::with_capacity(256);
+                let capacity = buffer.capacity();
+                for case in [
+                    SaoriResponse::ok_with(&request, "1", ["あいう"]),
+                    SaoriResponse::no_content(&request),
+                ] {
+                    let written = case.to_encoded_bytes_into(&mut buffer).unwrap();
+                    let expect: Vec<u8> = case
+                        .to_encoded_bytes()
+                        .unwrap()
+                        .iter()
+                        .map(|v| *v as u8)
+                        .collect();
+                    assert_eq!(buffer, expect);
+                    assert_eq!(written, expect.len());
+                }
+                assert_eq!(buffer.capacity(), capacity);
+            }
+
+            #[test]
+            fn failed_when_unencodable_value() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let case = SaoriResponse::ok_with(&request, "\u{1F600}", Vec::<String>::new());
+                let mut buffer = Vec::new();
+                assert!(matches!(
+                    case.to_encoded_bytes_into(&mut buffer),
+                    Err(SaoriResponseError::Unencodable(_))
+                ));
+            }
+        }
+
+        mod to_encoded_bytes_with_framing {
+            use super::*;
+
+            fn to_i8(raw: &[u8]) -> Vec<i8> {
+                raw.iter().map(|v| *v as i8).collect()
+            }
+
+            #[test]
+            fn checking_value_default() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::OK);
+                case.set_result("1");
                 assert_eq!(
-                    case,
-                    SaoriResponse {
-                        version: SaoriVersion::V1_0,
-                        status: SaoriStatus::OK,
-                        result: String::new(),
-                        values: vec!["".to_string(), "bbb002".to_string()],
-                        charset: SaoriCharset::ShiftJIS
-                    }
+                    case.to_encoded_bytes_with_framing(&SaoriFraming::default()),
+                    case.to_encoded_bytes()
+                );
+            }
+
+            #[test]
+            fn checking_value_lf_without_nul() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_status(SaoriStatus::OK);
+                case.set_result("1");
+                let framing = SaoriFraming::new(SaoriLineEnding::Lf, false, false);
+                let result = case.to_encoded_bytes_with_framing(&framing).unwrap();
+                assert_eq!(
+                    result,
+                    to_i8(b"SAORI/1.0 200 OK\nCharset: UTF-8\nResult: 1\n\n")
+                );
+            }
+
+            #[test]
+            fn checking_value_mixed_with_bom() {
+                let case = SaoriResponse::new_bad_request();
+                let framing = SaoriFraming::new(SaoriLineEnding::Mixed, true, true);
+                let result = case.to_encoded_bytes_with_framing(&framing).unwrap();
+                assert_eq!(
+                    result,
+                    to_i8(b"\xEF\xBB\xBFSAORI/1.0 400 Bad Request\r\nCharset: UTF-8\r\n\r\n\0")
                 );
             }
         }
 
-        mod set_values {
+        mod set_encode_policy {
+            use super::*;
+
+            fn response(policy: SaoriEncodePolicy) -> SaoriResponse {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::ok_with(&request, "a\u{1F600}", Vec::<String>::new());
+                case.set_encode_policy(policy);
+                case
+            }
+
+            #[test]
+            fn failed_when_strict() {
+                let case = response(SaoriEncodePolicy::Strict);
+                assert!(case.to_encoded_bytes_u8().is_err());
+            }
+
+            #[test]
+            fn checking_value_replace() {
+                let case = response(SaoriEncodePolicy::Replace);
+                let expect = b"SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: a?\r\n\r\n\0";
+                assert_eq!(case.to_encoded_bytes_u8().unwrap(), expect);
+                let mut written = Vec::new();
+                case.write_to(&mut written).unwrap();
+                assert_eq!(written, expect);
+                assert_eq!(
+                    case.into_encoded_bytes().unwrap(),
+                    SaoriResponse::into_ffi_bytes(expect.to_vec())
+                );
+            }
+
+            #[test]
+            fn checking_value_upgrade_to_utf8() {
+                let case = response(SaoriEncodePolicy::UpgradeToUtf8);
+                let expect = "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nResult: a\u{1F600}\r\n\r\n\0";
+                assert_eq!(case.to_string(), expect);
+                assert_eq!(case.to_encoded_bytes_u8().unwrap(), expect.as_bytes());
+                assert_eq!(
+                    case.into_encoded_bytes().unwrap(),
+                    SaoriResponse::into_ffi_bytes(expect.as_bytes().to_vec())
+                );
+            }
+
+            #[test]
+            fn keep_charset_when_upgrade_not_needed() {
+                let mut case = response(SaoriEncodePolicy::UpgradeToUtf8);
+                case.set_result("あ");
+                assert_eq!(
+                    case.to_string(),
+                    "SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: あ\r\n\r\n\0"
+                );
+            }
+
+            #[test]
+            fn checking_value_numeric_character_reference() {
+                let case = response(SaoriEncodePolicy::NumericCharacterReference);
+                let expect =
+                    b"SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: a&#128512;\r\n\r\n\0";
+                assert_eq!(case.to_encoded_bytes_u8().unwrap(), expect);
+                assert_eq!(
+                    case.to_encoded_bytes().unwrap(),
+                    SaoriResponse::into_ffi_bytes(expect.to_vec())
+                );
+            }
+
+            #[test]
+            fn checking_value_skip() {
+                let case = response(SaoriEncodePolicy::Skip);
+                let expect = b"SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: a\r\n\r\n\0";
+                assert_eq!(case.to_encoded_bytes_u8().unwrap(), expect);
+                assert_eq!(
+                    case.into_encoded_bytes().unwrap(),
+                    SaoriResponse::into_ffi_bytes(expect.to_vec())
+                );
+            }
+        }
+
+        mod to_encoded_bytes_with_policy {
             use super::*;
 
             #[test]
-            fn checking_value_no_empty() {
+            fn checking_value() {
                 let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
                 let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
-                let mut case = SaoriResponse::from_request(&request);
-                assert_eq!(case.status, SaoriStatus::NoContent);
-                let case_values = vec!["aaa".to_string(), "bbb".to_string()];
-                case.set_values(case_values.clone());
+                let case = SaoriResponse::ok_with(&request, "a\u{1F600}", Vec::<String>::new());
+                let result = case
+                    .to_encoded_bytes_with_policy(SaoriEncodePolicy::Replace)
+                    .unwrap();
                 assert_eq!(
-                    case,
-                    SaoriResponse {
-                        version: SaoriVersion::V1_0,
-                        status: SaoriStatus::OK,
-                        result: String::new(),
-                        values: case_values.clone(),
-                        charset: SaoriCharset::ShiftJIS
-                    }
+                    result,
+                    SaoriResponse::into_ffi_bytes(
+                        b"SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: a?\r\n\r\n\0".to_vec()
+                    )
+                );
+                let result = case
+                    .to_encoded_bytes_with_policy(SaoriEncodePolicy::UpgradeToUtf8)
+                    .unwrap();
+                assert_eq!(
+                    result,
+                    SaoriResponse::into_ffi_bytes(
+                        "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nResult: a\u{1F600}\r\n\r\n\0"
+                            .as_bytes()
+                            .to_vec()
+                    )
                 );
+                assert_eq!(case.encode_policy(), SaoriEncodePolicy::Strict);
+                assert!(case.to_encoded_bytes().is_err());
             }
 
             #[test]
-            fn checking_value_empty() {
+            fn failed_when_strict() {
                 let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
                 let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
-                let mut case = SaoriResponse::from_request(&request);
-                assert_eq!(case.status, SaoriStatus::NoContent);
-                let case_values = vec![];
-                case.set_values(case_values.clone());
-                assert_eq!(
-                    case,
-                    SaoriResponse {
-                        version: SaoriVersion::V1_0,
-                        status: SaoriStatus::NoContent,
-                        result: String::new(),
-                        values: case_values.clone(),
-                        charset: SaoriCharset::ShiftJIS
-                    }
-                );
+                let mut case = SaoriResponse::ok_with(&request, "a\u{1F600}", Vec::<String>::new());
+                case.set_encode_policy(SaoriEncodePolicy::Replace);
+                assert!(case
+                    .to_encoded_bytes_with_policy(SaoriEncodePolicy::Strict)
+                    .is_err());
             }
         }
 
-        mod on_change_result_and_value {
+        mod find_unencodable {
             use super::*;
 
             #[test]
-            fn no_content_when_value_actually_empty_and_no_result() {
+            fn none_when_encodable() {
                 let mut case = SaoriResponse::new_bad_request();
                 case.set_status(SaoriStatus::OK);
-                assert_eq!(case.status(), &SaoriStatus::OK);
-                let values = vec![String::new(), String::new(), String::new()];
-                case.set_values(values);
-                assert_eq!(case.status(), &SaoriStatus::NoContent);
+                case.set_result("あいう".to_string());
+                assert!(case.find_unencodable().is_none());
             }
 
             #[test]
-            fn no_content_when_empty_value_and_no_result() {
-                let mut case = SaoriResponse::new_bad_request();
-                case.set_status(SaoriStatus::OK);
-                assert_eq!(case.status(), &SaoriStatus::OK);
-                let values = vec![];
-                case.set_values(values);
-                assert_eq!(case.status(), &SaoriStatus::NoContent);
+            fn some_when_unencodable_result() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.set_result("a\u{1F600}".to_string());
+                let result = case.find_unencodable().unwrap();
+                assert_eq!(result.field(), &SaoriResponseField::Result);
+                assert_eq!(result.character(), '\u{1F600}');
+                assert_eq!(result.offset(), 1);
+                assert_eq!(
+                    result.remedy(),
+                    SaoriEncodeRemedy::SwitchCharset(SaoriCharset::UTF8)
+                );
             }
+        }
+
+        mod diagnostics {
+            use super::*;
 
             #[test]
-            fn ok_when_some_values() {
+            fn empty_when_encodable() {
                 let mut case = SaoriResponse::new_bad_request();
-                case.set_status(SaoriStatus::NoContent);
-                assert_eq!(case.status(), &SaoriStatus::NoContent);
-                let values = vec![String::new(), String::new(), String::new()];
-                case.set_values(values);
-                assert_eq!(case.status(), &SaoriStatus::NoContent);
-                case.set_value_at(3, "aaa".to_string());
-                assert_eq!(case.status(), &SaoriStatus::OK);
+                case.set_status(SaoriStatus::OK);
+                case.set_values(vec!["あいう".to_string()]);
+                assert!(case.diagnostics().is_empty());
             }
 
             #[test]
-            fn ok_when_result_is_some() {
-                let mut case = SaoriResponse::new_bad_request();
-                case.set_status(SaoriStatus::NoContent);
-                assert_eq!(case.status(), &SaoriStatus::NoContent);
-                let values = vec![String::new(), String::new(), String::new()];
-                case.set_values(values);
-                assert_eq!(case.status(), &SaoriStatus::NoContent);
-                case.set_result("aaa".to_string());
-                assert_eq!(case.status(), &SaoriStatus::OK);
+            fn checking_value_when_unencodable() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.set_result("\u{1F600}".to_string());
+                case.set_values(vec!["a\u{1F600}".to_string()]);
+                let result = case.diagnostics();
+                assert_eq!(result.len(), 2);
+                assert!(result.has_errors());
+                let locations: Vec<Option<&SaoriLocation>> =
+                    result.iter().map(|v| v.location()).collect();
+                assert_eq!(
+                    locations,
+                    vec![
+                        Some(&SaoriLocation::Field(SaoriResponseField::Result)),
+                        Some(&SaoriLocation::Field(SaoriResponseField::Value(0))),
+                    ]
+                );
             }
         }
 
-        mod to_encoded_bytes {
-            use encoding::{all::WINDOWS_31J, EncoderTrap, Encoding};
-
+        mod to_pretty_string {
             use super::*;
 
             #[test]
-            fn success_when_valid_request() {
+            fn checking_value() {
                 let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
                 let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
                 let mut case = SaoriResponse::from_request(&request);
                 case.set_result("1".to_string());
-                case.set_values(vec!["aaa".to_string(), "bbb".to_string()]);
-                let result = case.to_encoded_bytes().unwrap();
-                let expect_raw =
-                    "SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: 1\r\nValue0: aaa\r\nValue1: bbb\r\n\r\n\0";
-                let expect = WINDOWS_31J.encode(expect_raw, EncoderTrap::Strict).unwrap();
-                let expect: Vec<i8> = expect.iter().map(|v| *v as i8).collect();
-                assert_eq!(result, expect);
+                case.set_values(vec!["a\u{1F600}".to_string()]);
+                let expect = "SAORI response
+  Version : SAORI/1.0
+  Status  : 200 OK
+  Charset : Shift_JIS
+  Result  : 1
+  Value0  : a\u{1F600}
+  Error   : [2002] '\u{1F600}' (U+1F600) at byte 1 cannot be encoded in Shift_JIS";
+                assert_eq!(case.to_pretty_string(), expect);
             }
         }
 
@@ -481,6 +3934,27 @@ mod tests {
                 assert_eq!(result, expect);
             }
 
+            #[test]
+            fn checking_value_extended_status() {
+                let request_raw =
+                    "EXECUTE SAORI/1.0\r\nCharset: UTF-8\r\nSecurityLevel: External\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.set_status(SaoriStatus::Forbidden);
+                case.set_result("1");
+                assert_eq!(case.status(), &SaoriStatus::Forbidden);
+                assert_eq!(
+                    case.to_string(),
+                    "SAORI/1.0 403 Forbidden\r\nCharset: UTF-8\r\n\r\n\0"
+                );
+
+                case.set_status(SaoriStatus::Custom(299, "Partial".to_string()));
+                assert_eq!(
+                    case.to_string(),
+                    "SAORI/1.0 299 Partial\r\nCharset: UTF-8\r\n\r\n\0"
+                );
+            }
+
             #[test]
             fn checking_value_result_only() {
                 let request_raw = "GET Version SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
@@ -508,6 +3982,52 @@ mod tests {
                 assert_eq!(result, expect);
             }
 
+            #[test]
+            fn checking_value_with_headers() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let mut case = SaoriResponse::from_request(&request);
+                case.set_header("X-Elapsed", "10").unwrap();
+                case.set_values(vec!["aaa".to_string()]);
+                case.set_result("1".to_string());
+                let result = case.to_string();
+                let expect =
+                    "SAORI/1.0 200 OK\r\nCharset: Shift_JIS\r\nResult: 1\r\nValue0: aaa\r\nX-Elapsed: 10\r\n\r\n\0".to_string();
+
+                assert_eq!(result, expect);
+            }
+
+            #[test]
+            fn checking_value_header_order_is_stable() {
+                let build = || {
+                    SaoriResponseBuilder::new()
+                        .x_saori_header("Zeta", "z")
+                        .value("aaa")
+                        .header("X-Alpha", "a")
+                        .result("1")
+                        .x_saori_header("Zeta", "z2")
+                        .build()
+                        .unwrap()
+                        .to_string()
+                };
+                let expect =
+                    "SAORI/1.0 200 OK\r\nCharset: UTF-8\r\nResult: 1\r\nValue0: aaa\r\nX-SAORI-Zeta: z2\r\nX-Alpha: a\r\n\r\n\0";
+
+                assert_eq!(build(), expect);
+                assert_eq!(build(), build());
+            }
+
+            #[test]
+            fn checking_value_bad_request_with_headers() {
+                let mut case = SaoriResponse::new_bad_request();
+                case.set_header("X-Warning", "unknown function").unwrap();
+                let result = case.to_string();
+                let expect =
+                    "SAORI/1.0 400 Bad Request\r\nCharset: UTF-8\r\nX-Warning: unknown function\r\n\r\n\0".to_string();
+
+                assert_eq!(result, expect);
+            }
+
             #[test]
             fn checking_value_with_values_only() {
                 let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
@@ -522,4 +4042,349 @@ mod tests {
             }
         }
     }
+
+    mod saori_response_builder {
+        use super::*;
+
+        mod build {
+            use super::*;
+
+            #[test]
+            fn success_when_valid_values() {
+                let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+                let request = SaoriRequest::new(request_raw.as_bytes()).unwrap();
+                let result = SaoriResponseBuilder::from_request(&request)
+                    .result("1")
+                    .value("aaa")
+                    .values(["bbb", "ccc"])
+                    .build()
+                    .unwrap();
+                assert_eq!(
+                    result,
+                    SaoriResponse {
+                        version: SaoriVersion::V1_0,
+                        status: SaoriStatus::OK,
+                        result: "1".to_string(),
+                        values: vec!["aaa".to_string(), "bbb".to_string(), "ccc".to_string()],
+                        charset: SaoriCharset::ShiftJIS,
+                        sender: None,
+                        headers: vec![],
+                        encode_policy: SaoriEncodePolicy::Strict,
+                        empty_values: SaoriEmptyValues::Keep,
+                        max_size: None,
+                        status_locked: false,
+                        content_on_error: false,
+                    }
+                );
+            }
+
+            #[test]
+            fn success_when_status_locked() {
+                let result = SaoriResponseBuilder::new()
+                    .status(SaoriStatus::OK)
+                    .values([""])
+                    .status_locked(true)
+                    .build()
+                    .unwrap();
+                assert_eq!(result.status(), &SaoriStatus::OK);
+                assert!(result.is_status_locked());
+            }
+
+            #[test]
+            fn success_when_escape_sakura_script() {
+                let result = SaoriResponseBuilder::new()
+                    .result("50%")
+                    .values(["\\n", "a"])
+                    .escape_sakura_script(true)
+                    .build()
+                    .unwrap();
+                assert_eq!(result.result(), "50\\%");
+                assert_eq!(result.values(), &["\\\\n".to_string(), "a".to_string()]);
+            }
+
+            #[test]
+            fn no_content_when_empty() {
+                let result = SaoriResponseBuilder::new().build().unwrap();
+                assert_eq!(result.status(), &SaoriStatus::NoContent);
+            }
+
+            #[test]
+            fn failed_with_all_errors() {
+                let result = SaoriResponseBuilder::new()
+                    .charset(SaoriCharset::ShiftJIS)
+                    .result("a\r\nValue9: injected")
+                    .value("\u{1F600}")
+                    .value("b\n")
+                    .build();
+                assert_eq!(
+                    result,
+                    Err(vec![
+                        SaoriResponseBuildError::LineBreak(SaoriResponseField::Result),
+                        SaoriResponseBuildError::LineBreak(SaoriResponseField::Value(1)),
+                        SaoriResponseBuildError::Unencodable(SaoriResponseEncodeError {
+                            field: SaoriResponseField::Value(0),
+                            character: '\u{1F600}',
+                            offset: 0,
+                            charset: SaoriCharset::ShiftJIS,
+                        }),
+                    ])
+                );
+            }
+
+            #[test]
+            fn failed_when_invalid_header() {
+                let result = SaoriResponseBuilder::new()
+                    .result("1")
+                    .header("Charset", "UTF-8")
+                    .header("X-Warning", "a\nb")
+                    .build();
+                assert_eq!(
+                    result,
+                    Err(vec![
+                        SaoriResponseBuildError::InvalidHeader("Charset".to_string()),
+                        SaoriResponseBuildError::LineBreak(SaoriResponseField::Header(
+                            "X-Warning".to_string()
+                        )),
+                    ])
+                );
+            }
+
+            #[test]
+            fn success_when_unencodable_with_replace_policy() {
+                let result = SaoriResponseBuilder::new()
+                    .charset(SaoriCharset::ShiftJIS)
+                    .value("\u{1F600}")
+                    .encode_policy(SaoriEncodePolicy::Replace)
+                    .build()
+                    .unwrap();
+                assert_eq!(result.encode_policy(), SaoriEncodePolicy::Replace);
+                assert!(result.to_encoded_bytes().is_ok());
+            }
+
+            #[test]
+            fn failed_when_ok_without_content() {
+                let result = SaoriResponseBuilder::new().status(SaoriStatus::OK).build();
+                assert_eq!(result, Err(vec![SaoriResponseBuildError::MissingResult]));
+            }
+
+            #[test]
+            fn failed_when_too_large() {
+                let result = SaoriResponseBuilder::new().result("1").max_size(10).build();
+                assert_eq!(result, Err(vec![SaoriResponseBuildError::TooLarge(48)]));
+            }
+
+            #[test]
+            fn failed_with_every_problem() {
+                let result = SaoriResponseBuilder::new()
+                    .charset(SaoriCharset::ShiftJIS)
+                    .result("a\r\nb")
+                    .value("\u{1F600}")
+                    .max_size(10)
+                    .build();
+                assert_eq!(
+                    result,
+                    Err(vec![
+                        SaoriResponseBuildError::LineBreak(SaoriResponseField::Result),
+                        SaoriResponseBuildError::Unencodable(SaoriResponseEncodeError {
+                            field: SaoriResponseField::Value(0),
+                            character: '\u{1F600}',
+                            offset: 0,
+                            charset: SaoriCharset::ShiftJIS,
+                        }),
+                        SaoriResponseBuildError::TooLarge(66),
+                    ])
+                );
+
+                let result = SaoriResponseBuilder::new()
+                    .status(SaoriStatus::OK)
+                    .status_locked(true)
+                    .max_size(10)
+                    .build();
+                assert_eq!(
+                    result,
+                    Err(vec![
+                        SaoriResponseBuildError::MissingResult,
+                        SaoriResponseBuildError::TooLarge(37),
+                    ])
+                );
+            }
+        }
+    }
+
+    mod escape_sakura_script {
+        use super::*;
+
+        #[test]
+        fn checking_value() {
+            assert_eq!(escape_sakura_script("\\h\\s[0]%(x)"), "\\\\h\\\\s[0]\\%(x)");
+        }
+
+        #[test]
+        fn borrowed_when_nothing_to_escape() {
+            assert!(matches!(escape_sakura_script("abc"), Cow::Borrowed("abc")));
+        }
+    }
+
+    mod saori_typed_response_builder {
+        use super::*;
+
+        fn request() -> SaoriRequest {
+            let request_raw = "EXECUTE SAORI/1.0\r\nCharset: Shift_JIS\r\n\r\n\0";
+            SaoriRequest::new(request_raw.as_bytes()).unwrap()
+        }
+
+        mod build {
+            use super::*;
+
+            #[test]
+            fn checking_value_content() {
+                let result = SaoriResponse::typed_builder(&request())
+                    .sender("calc")
+                    .result("3")
+                    .value("a")
+                    .build()
+                    .unwrap();
+                assert_eq!(result.status(), &SaoriStatus::OK);
+                assert_eq!(result.charset(), &SaoriCharset::ShiftJIS);
+                assert_eq!(result.result(), "3");
+                assert_eq!(result.values(), &vec!["a".to_string()]);
+                assert_eq!(result.sender(), Some("calc"));
+            }
+
+            #[test]
+            fn checking_value_empty() {
+                let result = SaoriResponse::typed_builder(&request()).build().unwrap();
+                assert_eq!(result.status(), &SaoriStatus::NoContent);
+            }
+
+            #[test]
+            fn checking_value_error() {
+                let result = SaoriResponse::typed_builder(&request())
+                    .not_implemented()
+                    .x_saori_header("Reason", "unknown")
+                    .build()
+                    .unwrap();
+                assert_eq!(result.status(), &SaoriStatus::NotImplemented);
+                assert_eq!(result.header("X-SAORI-Reason"), Some("unknown"));
+            }
+
+            #[test]
+            fn failed_when_values_are_empty() {
+                let result = SaoriResponse::typed_builder(&request())
+                    .values(Vec::<String>::new())
+                    .build();
+                assert_eq!(result, Err(vec![SaoriResponseBuildError::MissingResult]));
+            }
+        }
+    }
+
+    mod saori_status {
+        use super::*;
+
+        mod to_string {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                let result: Vec<String> = SaoriStatus::ALL.iter().map(|v| v.to_string()).collect();
+                assert_eq!(
+                    result,
+                    vec![
+                        "200 OK",
+                        "204 No Content",
+                        "400 Bad Request",
+                        "403 Forbidden",
+                        "500 Internal Server Error",
+                        "501 Not Implemented",
+                        "503 Service Unavailable"
+                    ]
+                );
+            }
+
+            #[test]
+            fn checking_value_custom() {
+                let case = SaoriStatus::Custom(429, "Too Many Requests".to_string());
+                assert_eq!(case.to_string(), "429 Too Many Requests");
+                assert_eq!(case.class(), SaoriStatusClass::ClientError);
+            }
+        }
+
+        mod from_code {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                for status in SaoriStatus::ALL {
+                    assert_eq!(SaoriStatus::from_code(status.to_code()), status);
+                }
+                assert_eq!(
+                    SaoriStatus::from_code(418),
+                    SaoriStatus::Custom(418, String::new())
+                );
+            }
+        }
+
+        mod try_from {
+            use super::*;
+
+            #[test]
+            fn success_when_three_digits() {
+                assert_eq!(SaoriStatus::try_from(501), Ok(SaoriStatus::NotImplemented));
+                assert_eq!(
+                    SaoriStatus::try_from(999),
+                    Ok(SaoriStatus::Custom(999, String::new()))
+                );
+            }
+
+            #[test]
+            fn failed_when_not_three_digits() {
+                assert_eq!(
+                    SaoriStatus::try_from(99),
+                    Err(SaoriResponseError::InvalidStatus(99))
+                );
+                assert_eq!(
+                    SaoriStatus::try_from(1000),
+                    Err(SaoriResponseError::InvalidStatus(1000))
+                );
+            }
+        }
+
+        mod class {
+            use super::*;
+
+            #[test]
+            fn checking_value() {
+                assert_eq!(SaoriStatus::OK.class(), SaoriStatusClass::Success);
+                assert_eq!(SaoriStatus::NoContent.class(), SaoriStatusClass::Success);
+                assert_eq!(
+                    SaoriStatus::BadRequest.class(),
+                    SaoriStatusClass::ClientError
+                );
+                assert_eq!(
+                    SaoriStatus::InternalServerError.class(),
+                    SaoriStatusClass::ServerError
+                );
+            }
+
+            #[test]
+            fn checking_predicates() {
+                assert!(SaoriStatus::NoContent.is_success());
+                assert!(!SaoriStatus::NoContent.is_client_error());
+                assert!(SaoriStatus::BadRequest.is_client_error());
+                assert!(SaoriStatus::InternalServerError.is_server_error());
+                assert!(!SaoriStatus::InternalServerError.is_success());
+            }
+
+            #[test]
+            fn checking_predicates_extended() {
+                assert!(SaoriStatus::Forbidden.is_client_error());
+                assert!(SaoriStatus::NotImplemented.is_server_error());
+                assert!(SaoriStatus::ServiceUnavailable.is_error());
+                assert!(!SaoriStatus::OK.is_error());
+                assert!(SaoriStatus::Custom(299, String::new()).is_success());
+                let case = SaoriStatus::Custom(302, String::new());
+                assert!(!case.is_success() && !case.is_error());
+            }
+        }
+    }
 }